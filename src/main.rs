@@ -1,59 +1,179 @@
-mod solutions;
+use advent_of_code_2021::solutions::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::panic;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
 
-use crate::solutions::*;
+const USAGE: &str = "usage: advent-of-code-2021 [--workers N | -j N] [DAY | FIRST-LAST]...";
+
+macro_rules! both_parts {
+    ($day:ident) => {
+        vec![$day::part_1().to_string(), $day::part_2().to_string()]
+    };
+}
+
+// the answers to a day's puzzles, in order of the parts
+fn answers(day: usize) -> Vec<String> {
+    match day {
+        1 => both_parts!(day1),
+        2 => both_parts!(day2),
+        3 => both_parts!(day3),
+        4 => both_parts!(day4),
+        5 => both_parts!(day5),
+        6 => both_parts!(day6),
+        7 => both_parts!(day7),
+        8 => both_parts!(day8),
+        9 => both_parts!(day9),
+        10 => both_parts!(day10),
+        11 => both_parts!(day11),
+        12 => both_parts!(day12),
+        13 => both_parts!(day13),
+        14 => both_parts!(day14),
+        15 => both_parts!(day15),
+        16 => both_parts!(day16),
+        17 => both_parts!(day17),
+        18 => both_parts!(day18),
+        19 => {
+            let (ans_1, ans_2) = day19::solve();
+            vec![ans_1.to_string(), ans_2.to_string()]
+        }
+        20 => both_parts!(day20),
+        21 => both_parts!(day21),
+        22 => both_parts!(day22),
+        23 => both_parts!(day23),
+        24 => both_parts!(day24),
+        25 => vec![day25::part_1().to_string()],
+        _ => panic!("there is no day {}", day),
+    }
+}
+
+struct Options {
+    days: Vec<usize>,
+    workers: usize,
+}
+
+fn parse_day(arg: &str) -> Result<usize, String> {
+    match arg.parse() {
+        Ok(day) if (1..=25).contains(&day) => Ok(day),
+        _ => Err(format!("{} is not a day from 1 to 25", arg)),
+    }
+}
+
+// the days to run can be given one at a time or as ranges like 3-7. With none, every day runs
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut days = BTreeSet::new();
+    let mut workers = None;
+    while let Some(arg) = args.next() {
+        if arg == "--workers" || arg == "-j" {
+            let count = args.next().ok_or("--workers needs a number")?;
+            match count.parse() {
+                Ok(count) if count > 0 => workers = Some(count),
+                _ => return Err(format!("{} is not a number of workers", count)),
+            }
+        } else if let Some((first, last)) = arg.split_once('-') {
+            let (first, last) = (parse_day(first)?, parse_day(last)?);
+            if first > last {
+                return Err(format!("{} is not a range of days", arg));
+            }
+            days.extend(first..=last);
+        } else {
+            days.insert(parse_day(&arg)?);
+        }
+    }
+    if days.is_empty() {
+        days.extend(1..=25);
+    }
+    let workers = workers.unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()));
+    Ok(Options {
+        days: days.into_iter().collect(),
+        workers,
+    })
+}
+
+struct Outcome {
+    day: usize,
+    // None if the day panicked
+    answers: Option<Vec<String>>,
+    time: Duration,
+}
+
+// runs the days on a pool of worker threads, each taking the next day still to be done as soon as
+// it's free. Days finish in any order, but are reported in the order they were given
+fn run_days(days: &[usize], workers: usize, mut report: impl FnMut(Outcome)) {
+    let next_day = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..workers.min(days.len()) {
+            let sender = sender.clone();
+            let next_day = &next_day;
+            scope.spawn(move || {
+                while let Some(&day) = days.get(next_day.fetch_add(1, Ordering::Relaxed)) {
+                    let start = Instant::now();
+                    let answers = panic::catch_unwind(|| answers(day)).ok();
+                    let time = start.elapsed();
+                    sender.send(Outcome { day, answers, time }).unwrap();
+                }
+            });
+        }
+        // so that the receiver stops once every worker has finished
+        drop(sender);
+
+        let mut finished = BTreeMap::new();
+        let mut to_report = days.iter();
+        let mut waiting_for = to_report.next();
+        for outcome in receiver {
+            finished.insert(outcome.day, outcome);
+            while let Some(outcome) = waiting_for.and_then(|day| finished.remove(day)) {
+                report(outcome);
+                waiting_for = to_report.next();
+            }
+        }
+    });
+}
 
 fn main() {
-    println!("The answer to day 1, part 1 is {}", day1::part_1());
-    println!("The answer to day 1, part 2 is {}", day1::part_2());
-    println!("The answer to day 2, part 1 is {}", day2::part_1());
-    println!("The answer to day 2, part 2 is {}", day2::part_2());
-    println!("The answer to day 3, part 1 is {}", day3::part_1());
-    println!("The answer to day 3, part 2 is {}", day3::part_2());
-    println!("The answer to day 4, part 1 is {}", day4::part_1());
-    println!("The answer to day 4, part 2 is {}", day4::part_2());
-    println!("The answer to day 5, part 1 is {}", day5::part_1());
-    println!("The answer to day 5, part 2 is {}", day5::part_2());
-    println!("The answer to day 6, part 1 is {}", day6::part_1());
-    println!("The answer to day 6, part 2 is {}", day6::part_2());
-    println!("The answer to day 7, part 1 is {}", day7::part_1());
-    println!("The answer to day 7, part 2 is {}", day7::part_2());
-    println!("The answer to day 8, part 1 is {}", day8::part_1());
-    println!("The answer to day 8, part 2 is {}", day8::part_2());
-    println!("The answer to day 9, part 1 is {}", day9::part_1());
-    println!("The answer to day 9, part 2 is {}", day9::part_2());
-    println!("The answer to day 10, part 1 is {}", day10::part_1());
-    println!("The answer to day 10, part 2 is {}", day10::part_2());
-    println!("The answer to day 11, part 1 is {}", day11::part_1());
-    println!("The answer to day 11, part 2 is {}", day11::part_2());
-    println!("The answer to day 12, part 1 is {}", day12::part_1());
-    println!("The answer to day 12, part 2 is {}", day12::part_2());
-    println!("The answer to day 13, part 1 is {}", day13::part_1());
-    println!("The output in day 13, part 2 is:");
-    day13::part_2();
-    println!("The answer to day 14, part 1 is {}", day14::part_1());
-    println!("The answer to day 14, part 2 is {}", day14::part_2());
-    println!("The answer to day 15, part 1 is {}", day15::part_1());
-    println!("The answer to day 15, part 2 is {}", day15::part_2());
-    println!("The answer to day 16, part 1 is {}", day16::part_1());
-    println!("The answer to day 16, part 1 is {}", day16::part_2());
-    println!("The answer to day 17, part 1 is {}", day17::part_1());
-    println!("The answer to day 17, part 2 is {}", day17::part_2());
-    println!("The answer to day 18, part 1 is {}", day18::part_1());
-    println!("The answer to day 18, part 2 is {}", day18::part_2());
-    let (ans_1, ans_2) = day19::solve();
-    println!("The answer to day 19, part 1 is {}", ans_1);
-    println!("The answer to day 19, part 2 is {}", ans_2);
-    println!("The answer to day 20, part 1 is {}", day20::part_1());
-    println!("The answer to day 20, part 2 is {}", day20::part_2());
-    println!("The answer to day 21, part 1 is {}", day21::part_1());
-    println!("The answer to day 21, part 2 is {}", day21::part_2());
-    println!("The answer to day 22, part 1 is {}", day22::part_1());
-    println!("The answer to day 22, part 2 is {}", day22::part_2());
-    // warning, both day 23 parts - especially part 2 - take HOURS to calculate,
-    // even compiled in release mode!
-    println!("The answer to day 23, part 1 is {}", day23::part_1());
-    println!("The answer to day 23, part 2 is {}", day23::part_2());
-    println!("The answer to day 24, part 1 is {}", day24::part_1());
-    println!("The answer to day 24, part 2 is {}", day24::part_2());
-    println!("The answer to day 25, part 1 is {}", day25::part_1());
+    let options = parse_args(env::args().skip(1)).unwrap_or_else(|error| {
+        eprintln!("{}\n{}", error, USAGE);
+        process::exit(2);
+    });
+
+    let start = Instant::now();
+    let mut failed = vec![];
+    run_days(&options.days, options.workers, |outcome| {
+        match outcome.answers {
+            Some(answers) => {
+                for (part, answer) in answers.iter().enumerate() {
+                    println!(
+                        "The answer to day {}, part {} is {}",
+                        outcome.day,
+                        part + 1,
+                        answer
+                    );
+                }
+            }
+            None => {
+                println!("Day {} failed", outcome.day);
+                failed.push(outcome.day);
+            }
+        }
+        println!(
+            "Day {} took {:.3}s",
+            outcome.day,
+            outcome.time.as_secs_f64()
+        );
+    });
+    println!(
+        "Ran {} days with {} workers in {:.3}s",
+        options.days.len(),
+        options.workers,
+        start.elapsed().as_secs_f64()
+    );
+    if !failed.is_empty() {
+        eprintln!("Failed days: {:?}", failed);
+        process::exit(1);
+    }
 }
@@ -1,42 +1,103 @@
+use std::cmp::Ordering;
+use std::collections::VecDeque;
 use std::fs::File;
 use std::io::prelude::*;
+use std::io::{self, BufReader};
 
-fn read_file() -> Vec<i32> {
-    let mut file = File::open("./input/input1.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-    contents.lines().map(|n| n.parse().unwrap()).collect()
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Changes {
+    pub increases: usize,
+    pub decreases: usize,
+    pub unchanged: usize,
 }
 
-fn solve_part_1(v: Vec<i32>) -> i32 {
-    let mut count = 0;
-    let mut last = v[0];
-    for &n in v.iter().skip(1) {
-        if n > last {
-            count += 1;
+// compares the sum of each window of readings with the one before, one reading at a time, so
+// that it never needs more than the last few readings.
+// Two windows next to each other share all but their first and last readings, so comparing
+// their sums is the same as comparing the reading that has just come in with the one that's
+// just dropped out.
+pub struct WindowAnalyser {
+    window: usize,
+    recent: VecDeque<i64>,
+    changes: Changes,
+}
+
+impl WindowAnalyser {
+    // fails if the window is empty
+    pub fn new(window: usize) -> Result<WindowAnalyser, String> {
+        if window == 0 {
+            return Err("window size must be at least 1".to_owned());
         }
-        last = n;
+        Ok(WindowAnalyser {
+            window,
+            recent: VecDeque::with_capacity(window + 1),
+            changes: Changes::default(),
+        })
     }
-    count
-}
 
-fn solve_part_2(v: Vec<i32>) -> i32 {
-    let mut count = 0;
-    for n in 3..(v.len()) {
-        let previous = n - 3;
-        if v[n] > v[previous] {
-            count += 1;
+    pub fn push(&mut self, reading: i64) {
+        self.recent.push_back(reading);
+        if self.recent.len() > self.window {
+            let dropped = self.recent.pop_front().unwrap();
+            let changes = &mut self.changes;
+            match reading.cmp(&dropped) {
+                Ordering::Greater => changes.increases += 1,
+                Ordering::Less => changes.decreases += 1,
+                Ordering::Equal => changes.unchanged += 1,
+            }
         }
     }
-    count
+
+    // the changes between all the windows completed so far
+    pub fn changes(&self) -> Changes {
+        self.changes
+    }
+}
+
+pub fn analyse<I: IntoIterator<Item = i64>>(readings: I, window: usize) -> Result<Changes, String> {
+    let mut analyser = WindowAnalyser::new(window)?;
+    for reading in readings {
+        analyser.push(reading);
+    }
+    Ok(analyser.changes())
+}
+
+// reads one reading per line, stopping at the first that can't be read. Blank lines are skipped
+pub fn read_readings<R: BufRead>(reader: R) -> impl Iterator<Item = Result<i64, String>> {
+    reader
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, Ok(text) if text.trim().is_empty()))
+        .map(|(index, line)| {
+            let line = line.map_err(|e| format!("line {}: {}", index + 1, e))?;
+            line.trim()
+                .parse()
+                .map_err(|_| format!("line {}: invalid reading {}", index + 1, line))
+        })
+}
+
+pub fn analyse_reader<R: BufRead>(reader: R, window: usize) -> Result<Changes, String> {
+    let mut analyser = WindowAnalyser::new(window)?;
+    for reading in read_readings(reader) {
+        analyser.push(reading?);
+    }
+    Ok(analyser.changes())
+}
+
+// for piping readings in, eg. from a sonar sweep still in progress
+pub fn analyse_stdin(window: usize) -> Result<Changes, String> {
+    analyse_reader(io::stdin().lock(), window)
+}
+
+fn analyse_file(window: usize) -> Changes {
+    let file = File::open("./input/input1.txt").unwrap();
+    analyse_reader(BufReader::new(file), window).unwrap()
 }
 
-pub fn part_1() -> i32 {
-    let nums = read_file();
-    solve_part_1(nums)
+pub fn part_1() -> usize {
+    analyse_file(1).increases
 }
 
-pub fn part_2() -> i32 {
-    let nums = read_file();
-    solve_part_2(nums)
+pub fn part_2() -> usize {
+    analyse_file(3).increases
 }
@@ -1,204 +1,188 @@
 use std::fs::File;
 use std::io::prelude::*;
 
-#[derive(PartialEq, Copy, Clone)]
-enum BracketType {
-    Bracket,
-    Square,
-    Curly,
-    Angle,
-}
-
-impl BracketType {
-    fn error_code(&self) -> usize {
-        match self {
-            BracketType::Bracket => 3,
-            BracketType::Square => 57,
-            BracketType::Curly => 1197,
-            BracketType::Angle => 25137,
+// one kind of bracket in the language being checked, with the scores used by the puzzle for
+// a corrupted line ending on its closer, and for each of its closers needed to complete a line
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct BracketPair {
+    pub open: char,
+    pub close: char,
+    pub error_score: usize,
+    pub completion_score: usize,
+}
+
+// the table of bracket pairs making up a language
+#[derive(Debug, Clone)]
+pub struct Delimiters {
+    pairs: Vec<BracketPair>,
+}
+
+impl Delimiters {
+    // fails if any character is used more than once across the table, as then we couldn't
+    // tell which bracket it is meant to be
+    pub fn new(pairs: Vec<BracketPair>) -> Result<Delimiters, String> {
+        let mut seen = vec![];
+        for pair in &pairs {
+            for c in [pair.open, pair.close] {
+                if seen.contains(&c) {
+                    return Err(format!("character {} is used by more than one bracket", c));
+                }
+                seen.push(c);
+            }
         }
+        Ok(Delimiters { pairs })
     }
 
-    fn completion_score(&self) -> usize {
-        match self {
-            BracketType::Bracket => 1,
-            BracketType::Square => 2,
-            BracketType::Curly => 3,
-            BracketType::Angle => 4,
+    // the four bracket types from the puzzle
+    pub fn standard() -> Delimiters {
+        let pair = |open, close, error_score, completion_score| BracketPair {
+            open,
+            close,
+            error_score,
+            completion_score,
+        };
+        Delimiters {
+            pairs: vec![
+                pair('(', ')', 3, 1),
+                pair('[', ']', 57, 2),
+                pair('{', '}', 1197, 3),
+                pair('<', '>', 25137, 4),
+            ],
         }
     }
-}
-
-#[derive(PartialEq, Copy, Clone)]
-enum BracketDirection {
-    Open,
-    Closed,
-}
-
-#[derive(PartialEq, Copy, Clone)]
-struct Character {
-    type_: BracketType,
-    direction: BracketDirection,
-}
-
-impl Character {
-    fn is_opener(&self) -> bool {
-        self.direction == BracketDirection::Open
-    }
 
-    fn find_match(self) -> Self {
-        match self {
-            Character {
-                type_,
-                direction: BracketDirection::Open,
-            } => Character {
-                type_,
-                direction: BracketDirection::Closed,
-            },
-            Character {
-                type_,
-                direction: BracketDirection::Closed,
-            } => Character {
-                type_,
-                direction: BracketDirection::Open,
-            },
-        }
+    pub fn pairs(&self) -> &[BracketPair] {
+        &self.pairs
     }
-}
-
-// represents a chunk in the process of being parsed. It may or may not have been completely parsed
-struct Chunk {
-    stack: Vec<Character>,
-}
 
-impl Chunk {
-    fn new() -> Chunk {
-        Chunk { stack: vec![] }
+    fn with_opener(&self, c: char) -> Option<&BracketPair> {
+        self.pairs.iter().find(|pair| pair.open == c)
     }
 
-    fn push_stack(&mut self, char: Character) {
-        if !char.is_opener() {
-            panic!("can't push closing bracket to stack!");
-        }
-        self.stack.push(char);
+    fn with_closer(&self, c: char) -> Option<&BracketPair> {
+        self.pairs.iter().find(|pair| pair.close == c)
     }
+}
 
-    fn pop_stack(&mut self) -> Option<Character> {
-        self.stack.pop()
+// the result of checking one line. Columns count from 1, and the stack holds the brackets
+// still open at that point, innermost last
+#[derive(Debug, PartialEq, Clone)]
+pub enum Diagnostic {
+    Complete,
+    Incomplete {
+        stack: Vec<char>,
+        completion: String,
+    },
+    // expected is None if a closer was found when nothing was open
+    Corrupted {
+        column: usize,
+        expected: Option<char>,
+        found: char,
+        stack: Vec<char>,
+    },
+    UnknownCharacter {
+        column: usize,
+        found: char,
+        stack: Vec<char>,
+    },
+}
+
+pub struct SyntaxChecker {
+    delimiters: Delimiters,
+}
+
+impl SyntaxChecker {
+    pub fn new(delimiters: Delimiters) -> SyntaxChecker {
+        SyntaxChecker { delimiters }
     }
 
-    fn parse_character(&mut self, char: Character) -> Result<(), usize> {
-        if char.is_opener() {
-            self.push_stack(char);
-            Ok(())
-        } else {
-            if let Some(open_bracket) = self.pop_stack() {
-                let matching = open_bracket.find_match();
-                if char == matching {
-                    Ok(())
-                } else {
-                    Err(char.type_.error_code())
+    pub fn check(&self, line: &str) -> Diagnostic {
+        let mut stack = vec![];
+        for (idx, c) in line.chars().enumerate() {
+            let column = idx + 1;
+            if self.delimiters.with_opener(c).is_some() {
+                stack.push(c);
+            } else if self.delimiters.with_closer(c).is_some() {
+                let expected = stack
+                    .last()
+                    .map(|&open| self.delimiters.with_opener(open).unwrap().close);
+                if expected != Some(c) {
+                    return Diagnostic::Corrupted {
+                        column,
+                        expected,
+                        found: c,
+                        stack,
+                    };
                 }
+                stack.pop();
             } else {
-                Err(char.type_.error_code())
+                return Diagnostic::UnknownCharacter {
+                    column,
+                    found: c,
+                    stack,
+                };
             }
         }
+        if stack.is_empty() {
+            return Diagnostic::Complete;
+        }
+        let completion = stack
+            .iter()
+            .rev()
+            .map(|&open| self.delimiters.with_opener(open).unwrap().close)
+            .collect();
+        Diagnostic::Incomplete { stack, completion }
     }
-}
-
-struct CodeLine {
-    chars: Vec<Character>,
-}
 
-// parses the line of code into a chunk. Returns an error code (as in part 1)
-// if a syntax error was encountered, or a completion score (as in part 2) if parsed successfully
-// but incomplete
-impl CodeLine {
-    fn parse(&self) -> Result<usize, usize> {
-        let mut chunk = Chunk::new();
-        for &char in &self.chars {
-            if let Err(error_code) = chunk.parse_character(char) {
-                return Err(error_code);
+    // the score from part 1, only defined for corrupted lines
+    pub fn error_score(&self, diagnostic: &Diagnostic) -> Option<usize> {
+        match diagnostic {
+            Diagnostic::Corrupted { found, .. } => {
+                Some(self.delimiters.with_closer(*found).unwrap().error_score)
             }
+            _ => None,
         }
-        // if we reach here without returning, the whole line must have been successfully parsed.
-        // So we can compute the autocomplete score
-        let mut total_score = 0;
-        let mut stack = chunk.stack.clone();
-        stack.reverse();
-        for char in stack {
-            let score = char.type_.completion_score();
-            total_score *= 5;
-            total_score += score;
-        }
-        Ok(total_score)
     }
-}
 
-fn parse_char(c: char) -> Character {
-    match c {
-        '(' => Character {
-            type_: BracketType::Bracket,
-            direction: BracketDirection::Open,
-        },
-        ')' => Character {
-            type_: BracketType::Bracket,
-            direction: BracketDirection::Closed,
-        },
-        '[' => Character {
-            type_: BracketType::Square,
-            direction: BracketDirection::Open,
-        },
-        ']' => Character {
-            type_: BracketType::Square,
-            direction: BracketDirection::Closed,
-        },
-        '{' => Character {
-            type_: BracketType::Curly,
-            direction: BracketDirection::Open,
-        },
-        '}' => Character {
-            type_: BracketType::Curly,
-            direction: BracketDirection::Closed,
-        },
-        '<' => Character {
-            type_: BracketType::Angle,
-            direction: BracketDirection::Open,
-        },
-        '>' => Character {
-            type_: BracketType::Angle,
-            direction: BracketDirection::Closed,
-        },
-        _ => panic!("unexpected character {}", c),
+    // the score from part 2, only defined for incomplete lines
+    pub fn completion_score(&self, diagnostic: &Diagnostic) -> Option<usize> {
+        match diagnostic {
+            Diagnostic::Incomplete { completion, .. } => {
+                let mut total_score = 0;
+                for c in completion.chars() {
+                    total_score *= 5;
+                    total_score += self.delimiters.with_closer(c).unwrap().completion_score;
+                }
+                Some(total_score)
+            }
+            _ => None,
+        }
     }
 }
 
-fn parse_line(line: &str) -> CodeLine {
-    let chars = line.chars().map(parse_char).collect();
-    CodeLine { chars }
-}
-
-fn read_file() -> Vec<CodeLine> {
+fn read_file() -> Vec<String> {
     let mut file = File::open("./input/input10.txt").unwrap();
     let mut contents = String::new();
     file.read_to_string(&mut contents).unwrap();
-    contents.lines().map(parse_line).collect()
+    contents.lines().map(|line| line.to_owned()).collect()
 }
 
-fn solve_part_1(code: Vec<CodeLine>) -> usize {
+fn solve_part_1(code: Vec<String>) -> usize {
+    let checker = SyntaxChecker::new(Delimiters::standard());
     let mut total = 0;
     for line in code {
-        if let Err(code) = line.parse() {
-            total += code;
+        if let Some(score) = checker.error_score(&checker.check(&line)) {
+            total += score;
         }
     }
     total
 }
 
-fn solve_part_2(code: Vec<CodeLine>) -> usize {
+fn solve_part_2(code: Vec<String>) -> usize {
+    let checker = SyntaxChecker::new(Delimiters::standard());
     let mut scores = vec![];
     for line in code {
-        if let Ok(score) = line.parse() {
+        if let Some(score) = checker.completion_score(&checker.check(&line)) {
             scores.push(score);
         }
     }
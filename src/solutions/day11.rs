@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
 
@@ -7,22 +8,46 @@ struct Octopus {
 }
 
 impl Octopus {
-    // gives the octopus an additional energy, and returns true if it causes a flash
-    fn gain_energy(&mut self) -> bool {
-        let will_flash = self.energy == 9;
-        self.energy += 1;
+    // gives the octopus an additional energy, and returns true if it causes a flash - which
+    // happens when its energy goes above the threshold
+    fn gain_energy(&mut self, threshold: u8) -> bool {
+        let will_flash = self.energy == threshold;
+        self.energy = self.energy.saturating_add(1);
         will_flash
     }
 
-    fn reset(&mut self) {
-        if self.energy > 9 {
+    fn reset(&mut self, threshold: u8) {
+        if self.energy > threshold {
             self.energy = 0;
         }
     }
 }
 
-struct OctopusGrid {
+// a single flash during a step. The wave is 0 for octopuses flashing from the energy every
+// octopus gains at the start of the step, 1 for those set off by those flashes, and so on.
+// The order counts all flashes in the step from 0, in the order they happened
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Flash {
+    pub row: usize,
+    pub col: usize,
+    pub wave: usize,
+    pub order: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Synchronisation {
+    // the first step on which every octopus flashed
+    Synchronised { step: usize },
+    // the grid after `step` steps was the same as after `previous` steps, without the octopuses
+    // having all flashed together in between - so they never will
+    Never { step: usize, previous: usize },
+    // the octopuses hadn't all flashed together by the step limit
+    NotWithin { steps: usize },
+}
+
+pub struct OctopusGrid {
     grid: Vec<Vec<Octopus>>,
+    threshold: u8,
 }
 
 impl OctopusGrid {
@@ -94,14 +119,14 @@ impl OctopusGrid {
 
     fn gain_energy(&mut self, row: usize, col: usize) -> bool {
         let mut octopus = self.get_octopus(row, col);
-        let has_flashed = octopus.gain_energy();
+        let has_flashed = octopus.gain_energy(self.threshold);
         self.grid[row][col] = octopus;
         has_flashed
     }
 
     fn reset(&mut self, row: usize, col: usize) {
         let mut octopus = self.get_octopus(row, col);
-        octopus.reset();
+        octopus.reset(self.threshold);
         self.grid[row][col] = octopus;
     }
 
@@ -141,61 +166,138 @@ impl OctopusGrid {
         coords
     }
 
-    // does the full recursive process
-    fn do_step(&mut self) -> usize {
-        let mut total_flashes = 0;
+    // does the full recursive process, returning every flash that happened
+    pub fn do_step(&mut self) -> Vec<Flash> {
+        let mut all_flashes = vec![];
         let mut to_increase = self.get_all_coords();
-        loop {
+        for wave in 0.. {
             let flashes = self.gain_energy_all(to_increase);
-            let num_flashes = flashes.len();
-            if num_flashes == 0 {
-                // no more flashes, so we can read the answer
+            if flashes.is_empty() {
+                // no more flashes, so the step is over
                 break;
-            } else {
-                total_flashes += num_flashes;
-                to_increase = self.get_all_neighbours(flashes);
             }
+            for &(row, col) in &flashes {
+                all_flashes.push(Flash {
+                    row,
+                    col,
+                    wave,
+                    order: all_flashes.len(),
+                });
+            }
+            to_increase = self.get_all_neighbours(flashes);
         }
         self.reset_all();
-        total_flashes
+        all_flashes
     }
 
-    fn do_steps(&mut self, num_steps: usize) -> usize {
+    pub fn do_steps(&mut self, num_steps: usize) -> usize {
         let mut total = 0;
         for _ in 0..num_steps {
-            total += self.do_step();
+            total += self.do_step().len();
         }
         total
     }
 
-    fn steps_till_all_flash(&mut self) -> usize {
+    fn energies(&self) -> Vec<u8> {
+        self.grid
+            .iter()
+            .flat_map(|row| row.iter().map(|octopus| octopus.energy))
+            .collect()
+    }
+
+    // steps until every octopus flashes at once, or the given number of steps (if any) have
+    // been done. Every grid seen is remembered, so that if one comes round again we know the
+    // octopuses are stuck in a loop that doesn't include all of them flashing together
+    pub fn steps_till_all_flash(&mut self, limit: Option<usize>) -> Synchronisation {
         let size = self.grid_size();
+        let mut seen = HashMap::new();
+        seen.insert(self.energies(), 0);
         let mut step_number = 1;
         loop {
+            if limit.is_some_and(|limit| step_number > limit) {
+                return Synchronisation::NotWithin {
+                    steps: step_number - 1,
+                };
+            }
             let flashes = self.do_step();
-            if flashes == size {
-                break step_number;
+            if flashes.len() == size {
+                return Synchronisation::Synchronised { step: step_number };
+            }
+            if let Some(&previous) = seen.get(&self.energies()) {
+                return Synchronisation::Never {
+                    step: step_number,
+                    previous,
+                };
             }
+            seen.insert(self.energies(), step_number);
             step_number += 1;
         }
     }
 }
 
-fn parse_digit(c: char) -> Octopus {
-    let energy = c.to_string().parse().unwrap();
-    Octopus { energy }
+fn parse_digit(c: char) -> Option<Octopus> {
+    c.to_digit(10).map(|energy| Octopus {
+        energy: energy as u8,
+    })
 }
 
-fn parse_line(line: &str) -> Vec<Octopus> {
-    line.chars().map(parse_digit).collect()
+// fails on anything that isn't a digit, saying where it is
+fn parse_line(line: &str, row: usize) -> Result<Vec<Octopus>, String> {
+    line.chars()
+        .enumerate()
+        .map(|(col, c)| {
+            parse_digit(c).ok_or_else(|| {
+                format!(
+                    "found {:?} rather than a digit at row {}, column {}",
+                    c,
+                    row + 1,
+                    col + 1
+                )
+            })
+        })
+        .collect()
+}
+
+impl OctopusGrid {
+    // fails if the grid is empty, not rectangular or not all digits, or any octopus starts with
+    // more energy than the threshold for flashing. The threshold can't be the most energy an
+    // octopus can hold, since then its energy could never go above it
+    pub fn parse(lines: &[&str], threshold: u8) -> Result<OctopusGrid, String> {
+        if threshold == u8::MAX {
+            return Err(format!("the threshold must be less than {}", u8::MAX));
+        }
+        let grid: Vec<Vec<Octopus>> = lines
+            .iter()
+            .enumerate()
+            .map(|(row, line)| parse_line(line, row))
+            .collect::<Result<_, _>>()?;
+        let width = grid.first().map_or(0, |row| row.len());
+        if width == 0 {
+            return Err("the grid is empty".to_owned());
+        }
+        if grid.iter().any(|row| row.len() != width) {
+            return Err("the grid rows are not all the same length".to_owned());
+        }
+        if grid
+            .iter()
+            .flatten()
+            .any(|octopus| octopus.energy > threshold)
+        {
+            return Err(format!(
+                "an octopus starts with more than {} energy",
+                threshold
+            ));
+        }
+        Ok(OctopusGrid { grid, threshold })
+    }
 }
 
 fn read_file() -> OctopusGrid {
     let mut file = File::open("./input/input11.txt").unwrap();
     let mut contents = String::new();
     file.read_to_string(&mut contents).unwrap();
-    let grid = contents.lines().map(parse_line).collect();
-    OctopusGrid { grid }
+    let lines: Vec<&str> = contents.lines().collect();
+    OctopusGrid::parse(&lines, 9).unwrap()
 }
 
 fn solve_part_1(mut octopuses: OctopusGrid) -> usize {
@@ -208,7 +310,10 @@ pub fn part_1() -> usize {
 }
 
 fn solve_part_2(mut octopuses: OctopusGrid) -> usize {
-    octopuses.steps_till_all_flash()
+    match octopuses.steps_till_all_flash(None) {
+        Synchronisation::Synchronised { step } => step,
+        result => panic!("octopuses don't all flash together: {:?}", result),
+    }
 }
 
 pub fn part_2() -> usize {
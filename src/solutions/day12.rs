@@ -1,10 +1,10 @@
-use std::collections::HashMap;
-use std::convert::TryInto;
-use std::fmt;
+use super::parse::{lines, literal, map, parse_all, word, Input, ParseResult};
+use super::search::{self, Graph};
+use std::collections::{BTreeSet, HashMap};
 use std::fs::File;
 use std::io::prelude::*;
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
 struct Room {
     name: String,
 }
@@ -13,7 +13,7 @@ impl Room {
     fn is_large(&self) -> bool {
         // names are either all upper or all lower so just test first character
         // for uppercase
-        self.name.chars().nth(0).unwrap().is_uppercase()
+        self.name.chars().next().unwrap().is_uppercase()
     }
 }
 
@@ -21,266 +21,122 @@ struct Connection {
     rooms: [Room; 2],
 }
 
-#[derive(PartialEq, Clone)]
-struct Path {
-    path: Vec<Room>,
+// where a path has got to, along with what matters about where it's been - which is the small
+// rooms it can't go back into
+#[derive(PartialEq, Eq, Hash, Clone)]
+struct Visit<'a> {
+    room: &'a Room,
+    small_visited: BTreeSet<&'a Room>,
     // property needed for part 2
     visited_small_twice: bool,
 }
 
-// for my own debugging - but useful enough to keep in!
-impl fmt::Debug for Path {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            self.path
-                .iter()
-                .map(|room| room.name.to_owned())
-                .collect::<Vec<String>>()
-                .join("-")
-        )
-    }
-}
-
-impl Path {
-    // tests a room against a path to see if it's valid
-    fn is_valid(&self, room: &Room) -> bool {
-        let mut is_valid = true;
-        let is_small = !room.is_large();
-        if is_small {
-            // a small room isn't valid if it's been visited already
-            if let Some(_) = self.path.iter().find(|&visited| visited == room) {
-                is_valid = false;
-            }
-        }
-        is_valid
-    }
-
-    // adjusted version for part 2.
-    // Returns a second bool to indicate if a small room was visited for the first time or
-    // not, so that we can update the visited_small_twice property if this happens.
-    // (Note, we don't do this in here making &self mutable, as then it would get tricky to
-    // filter by this function - the property would be updated at the wrong time!)
-    fn is_valid_2(&self, room: &Room) -> (bool, bool) {
-        let mut is_valid = true;
-        let mut visited_small_again = false;
-        let is_small = !room.is_large();
-        if is_small {
-            // check how many times it's been visited already
-            let previous_visits = self.path.iter().filter(|&visited| visited == room).count();
-            // check if any small rooms have been visited twice already
-            if previous_visits > 1 {
-                is_valid = false;
-            } else if previous_visits == 1 {
-                if self.visited_small_twice || room.name == "start" || room.name == "end" {
-                    is_valid = false;
-                } else {
-                    visited_small_again = true;
-                }
-            }
-        }
-        (is_valid, visited_small_again)
-    }
-
-    fn add_room(&self, room: Room) -> Self {
-        let mut new_path = self.clone();
-        new_path.path.push(room);
-        new_path
-    }
-}
-
 struct Map {
     connections: Vec<Connection>,
 }
 
 impl Map {
-    fn find_connections(&self, room: &Room) -> Vec<&Room> {
-        let mut result = vec![];
-        for connection in &self.connections {
-            if &connection.rooms[0] == room {
-                result.push(&connection.rooms[1]);
-            }
-            if &connection.rooms[1] == room {
-                result.push(&connection.rooms[0]);
-            }
-        }
-        result
-    }
-
-    // used to cache the results of calling find_connection, so that it doesn't have to be computed
-    // at every step of the recursive path-finding algorithm
-    fn connections_cached(&self) -> HashMap<Room, Vec<&Room>> {
-        let mut connections = HashMap::new();
+    // the rooms each room is connected to
+    fn connections_cached(&self) -> HashMap<&Room, Vec<&Room>> {
+        let mut connections: HashMap<&Room, Vec<&Room>> = HashMap::new();
         for Connection {
             rooms: [start, end],
         } in &self.connections
         {
-            for room in [start.clone(), end.clone()] {
-                if let None = connections.get(&room) {
-                    let connected = self.find_connections(&room);
-                    connections.insert(room, connected);
-                }
-            }
+            connections.entry(start).or_default().push(end);
+            connections.entry(end).or_default().push(start);
         }
         connections
     }
 
-    fn count_all_paths_recursive(
-        &self,
-        connections: &HashMap<Room, Vec<&Room>>,
-        start: Room,
-        target: Room,
-        so_far: Path,
-        mut count: &mut usize,
-    ) -> usize {
-        let current_room = so_far.path.iter().last().unwrap();
-        let current_path = so_far.clone();
-        if current_room == &target {
-            // we've reached the target so can just add 1 to the number of paths
-            *count += 1;
-            // we shouldn't continue any more (as no path, even for part 2, can reach the end twice)
-            // The return value is actually irrelevant but we need something of the correct type.
-            return 0;
-        }
-        let can_go_next = connections.get(current_room).unwrap();
-        for &next in can_go_next
-            .iter()
-            .filter(|room| current_path.is_valid(room))
-        {
-            let extended = current_path.add_room(next.clone());
-            // now the recursive call to find the way forward from here
-            self.count_all_paths_recursive(
-                connections,
-                start.clone(),
-                target.clone(),
-                extended,
-                &mut count,
-            );
-        }
-        *count
+    fn count_paths(&self, start: &Room, end: &Room, can_revisit: bool) -> usize {
+        let caves = Caves {
+            connections: self.connections_cached(),
+            can_revisit,
+        };
+        let first_visit = Visit {
+            room: start,
+            small_visited: BTreeSet::from([start]),
+            visited_small_twice: false,
+        };
+        search::all_paths(&caves, first_visit, |visit| visit.room == end).count()
     }
+}
 
-    // mostly a copy of the previous one, but uses is_valid_2 rather than is_valid,
-    // with changes a bit trickier than can be easily done by just passing a function parameter
-    fn count_all_paths_recursive_2(
-        &self,
-        connections: &HashMap<Room, Vec<&Room>>,
-        start: Room,
-        target: Room,
-        so_far: Path,
-        mut count: &mut usize,
-    ) -> usize {
-        let current_room = so_far.path.iter().last().unwrap();
-        if current_room == &target {
-            // we've reached the target so can just add 1 to the number of paths
-            *count += 1;
-            // we shouldn't continue any more (as no path, even for part 2, can reach the end twice)
-            // The return value is actually irrelevant but we need something of the correct type.
-            return 0;
-        }
-        let can_go_next = connections.get(current_room).unwrap();
-        for &next in can_go_next {
-            let (is_valid, update_flag) = so_far.is_valid_2(next);
-            if is_valid {
-                let mut current_path = so_far.clone();
-                if update_flag {
-                    current_path.visited_small_twice = true;
+// the graph of every way through the caves. Each node is a room along with the small rooms
+// visited on the way to it, so that the only ways on from it are those allowed by the rules -
+// which means there are no cycles, and every path can be found with a depth-first search
+struct Caves<'a> {
+    connections: HashMap<&'a Room, Vec<&'a Room>>,
+    // whether a single small room can be visited twice, as in part 2
+    can_revisit: bool,
+}
+
+impl<'a> Graph for Caves<'a> {
+    type Node = Visit<'a>;
+
+    fn successors(&self, visit: &Visit<'a>) -> Vec<(Visit<'a>, usize)> {
+        let mut next_visits = vec![];
+        for &room in &self.connections[visit.room] {
+            let mut next = Visit {
+                room,
+                ..visit.clone()
+            };
+            if !room.is_large() && !next.small_visited.insert(room) {
+                // visited already - which is only allowed once, and never for the start or end
+                if !self.can_revisit
+                    || visit.visited_small_twice
+                    || room.name == "start"
+                    || room.name == "end"
+                {
+                    continue;
                 }
-                let extended = current_path.add_room(next.clone());
-                // now the recursive call to find the way forward from here
-                self.count_all_paths_recursive_2(
-                    connections,
-                    start.clone(),
-                    target.clone(),
-                    extended,
-                    &mut count,
-                );
+                next.visited_small_twice = true;
             }
+            next_visits.push((next, 1));
         }
-        *count
-    }
-
-    fn count_paths(&self, start: Room, end: Room) -> usize {
-        let connections = self.connections_cached();
-        self.count_all_paths_recursive(
-            &connections,
-            start.clone(),
-            end,
-            Path {
-                path: vec![start],
-                visited_small_twice: false,
-            },
-            &mut 0,
-        )
-    }
-
-    fn count_paths_2(&self, start: Room, end: Room) -> usize {
-        let connections = self.connections_cached();
-        self.count_all_paths_recursive_2(
-            &connections,
-            start.clone(),
-            end,
-            Path {
-                path: vec![start],
-                visited_small_twice: false,
-            },
-            &mut 0,
-        )
+        next_visits
     }
 }
 
-fn parse_room(room: &str) -> Room {
-    let name = room.to_owned();
-    Room { name }
+fn parse_room(input: Input) -> ParseResult<Room> {
+    map(word, |name| Room {
+        name: name.to_owned(),
+    })(input)
 }
 
-fn parse_line(line: &str) -> Connection {
-    let rooms = line
-        .split("-")
-        .map(parse_room)
-        .collect::<Vec<Room>>()
-        .try_into()
-        .unwrap();
-    Connection { rooms }
+fn parse_connection(input: Input) -> ParseResult<Connection> {
+    let (start, input) = parse_room(input)?;
+    let (_, input) = literal("-")(input)?;
+    let (end, input) = parse_room(input)?;
+    let rooms = [start, end];
+    Ok((Connection { rooms }, input))
 }
 
 fn read_file() -> Map {
     let mut file = File::open("./input/input12.txt").unwrap();
     let mut contents = String::new();
     file.read_to_string(&mut contents).unwrap();
-    let connections = contents.lines().map(parse_line).collect();
+    let connections = parse_all(&contents, lines(parse_connection)).unwrap();
     Map { connections }
 }
 
-fn solve_part_1(map: Map) -> usize {
-    map.count_paths(
-        Room {
-            name: String::from("start"),
-        },
-        Room {
-            name: String::from("end"),
-        },
-    )
-}
-
-fn solve_part_2(map: Map) -> usize {
-    map.count_paths_2(
-        Room {
-            name: String::from("start"),
-        },
-        Room {
-            name: String::from("end"),
-        },
-    )
+fn solve(map: Map, can_revisit: bool) -> usize {
+    let start = Room {
+        name: String::from("start"),
+    };
+    let end = Room {
+        name: String::from("end"),
+    };
+    map.count_paths(&start, &end, can_revisit)
 }
 
 pub fn part_1() -> usize {
     let map = read_file();
-    solve_part_1(map)
+    solve(map, false)
 }
 
 pub fn part_2() -> usize {
     let map = read_file();
-    solve_part_2(map)
+    solve(map, true)
 }
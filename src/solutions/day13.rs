@@ -1,146 +1,171 @@
-use itertools::Itertools;
-use std::collections::HashMap;
+use super::geometry::{Box2, Point2};
+use super::ocr;
+use super::parse::{keyword, lines, literal, parse_all, section, unsigned, Input, ParseResult};
+use std::collections::HashSet;
 use std::fs::File;
 use std::io::prelude::*;
 
-#[derive(Clone, Copy)]
-enum Axis {
+#[derive(Clone, Copy, Debug)]
+pub enum Axis {
     X,
     Y,
 }
 
-#[derive(Clone, Copy)]
-struct Fold {
-    axis: Axis,
-    position: usize,
+#[derive(Clone, Copy, Debug)]
+pub struct Fold {
+    pub axis: Axis,
+    pub position: usize,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
-struct CoOrd {
-    x: usize,
-    y: usize,
+impl Axis {
+    fn of(&self, dot: &Point2) -> isize {
+        match self {
+            Axis::X => dot.x,
+            Axis::Y => dot.y,
+        }
+    }
+
+    // the dot moved to the given value along this axis
+    fn set(&self, dot: &Point2, value: isize) -> Point2 {
+        match self {
+            Axis::X => Point2 { x: value, ..*dot },
+            Axis::Y => Point2 { y: value, ..*dot },
+        }
+    }
 }
 
-struct PuzzleData {
-    points: Vec<CoOrd>,
-    folds: Vec<Fold>,
+pub struct Paper {
+    dots: HashSet<Point2>,
+    width: usize,
+    height: usize,
 }
 
-impl PuzzleData {
-    fn do_fold(&self, fold: Fold) -> Vec<CoOrd> {
-        let mut new_points: Vec<CoOrd> = vec![];
-        let Fold { axis, position } = fold;
-        for &point in &self.points {
-            let to_compare = match axis {
-                Axis::X => point.x,
-                Axis::Y => point.y,
-            };
-            if to_compare != position {
-                let new_point = if to_compare < position {
-                    point
-                } else {
-                    match axis {
-                        Axis::X => CoOrd {
-                            x: 2 * position - point.x,
-                            ..point
-                        },
-                        Axis::Y => CoOrd {
-                            y: 2 * position - point.y,
-                            ..point
-                        },
-                    }
-                };
-                // note: we do NOT check here that the point does not exist in the
-                // new_points vector. This would be too much of a performance hit to do
-                // all the time. We do it at the end, efficiently using a hashmap.
-                new_points.push(new_point);
-            }
+impl Paper {
+    // the paper is taken to be just big enough to hold all the dots, starting from (0, 0)
+    pub fn new(dots: &[Point2]) -> Paper {
+        let (width, height) = Box2::bounding(dots.iter().copied()).map_or((0, 0), |bounds| {
+            (bounds.max.x as usize + 1, bounds.max.y as usize + 1)
+        });
+        Paper {
+            dots: dots.iter().copied().collect(),
+            width,
+            height,
         }
-        new_points
     }
 
-    fn do_fold_mut(&mut self, fold: Fold) {
-        let new_points = self.do_fold(fold);
-        self.points = new_points;
+    pub fn num_dots(&self) -> usize {
+        self.dots.len()
     }
 
-    fn complete_fold(&mut self) {
-        for fold in self.folds.clone() {
-            self.do_fold_mut(fold);
+    fn size_along(&self, axis: Axis) -> usize {
+        match axis {
+            Axis::X => self.width,
+            Axis::Y => self.height,
         }
     }
 
-    fn do_print(&self) {
-        let all_points = get_uniques(&self.points);
-        let xs = all_points.iter().map(|CoOrd { x, .. }| x);
-        let ys = all_points.iter().map(|CoOrd { y, .. }| y);
-        let &min_x = xs.clone().min().unwrap();
-        let &max_x = xs.max().unwrap();
-        let &min_y = ys.clone().min().unwrap();
-        let &max_y = ys.max().unwrap();
-        for y in min_y..=max_y {
-            for x in min_x..=max_x {
-                if all_points.contains(&CoOrd {x, y}) {
-                    print!("#");
-                } else {
-                    print!(".");
-                }
-            }
-            println!();
+    // folds the part of the paper after the line over onto the part before it. The fold
+    // doesn't have to be in the middle: if the part being folded over is the bigger one, it
+    // sticks out past the edge of the other, and the co-ordinates are shifted so that
+    // everything is still at 0 or more. Either way, a dot ends up as far from the new far edge
+    // as it was from the fold line.
+    // Fails if the line is off the paper or goes through a dot, leaving the paper unchanged.
+    pub fn fold(&mut self, fold: Fold) -> Result<(), String> {
+        let Fold { axis, position } = fold;
+        let size = self.size_along(axis);
+        if position >= size {
+            return Err(format!("{:?} is outside the paper", fold));
+        }
+        if let Some(dot) = self
+            .dots
+            .iter()
+            .find(|dot| axis.of(dot) == position as isize)
+        {
+            return Err(format!("{:?} goes through the dot at {:?}", fold, dot));
+        }
+        let new_size = position.max(size - position - 1);
+        self.dots = self
+            .dots
+            .iter()
+            .map(|dot| {
+                let distance = axis.of(dot).abs_diff(position as isize);
+                axis.set(dot, (new_size - distance) as isize)
+            })
+            .collect();
+        match axis {
+            Axis::X => self.width = new_size,
+            Axis::Y => self.height = new_size,
         }
+        Ok(())
     }
-}
 
-// general utility to efficiently get the unique elements in a vector
-fn get_uniques(v: &Vec<CoOrd>) -> Vec<CoOrd> {
-    let mut all: HashMap<CoOrd, ()> = HashMap::new();
-    for &point in v {
-        all.insert(point, ());
+    // does each fold in turn, giving the number of dots visible after each one
+    pub fn fold_all(&mut self, folds: &[Fold]) -> Result<Vec<usize>, String> {
+        let mut counts = vec![];
+        for &fold in folds {
+            self.fold(fold)?;
+            counts.push(self.num_dots());
+        }
+        Ok(counts)
+    }
+
+    // the dots as a grid of pixels, starting from (0, 0)
+    pub fn to_grid(&self) -> Vec<Vec<bool>> {
+        let mut grid = vec![vec![false; self.width]; self.height];
+        for &Point2 { x, y } in &self.dots {
+            grid[y as usize][x as usize] = true;
+        }
+        grid
+    }
+
+    // the letters shown by the dots, or if they can't be read, the dots themselves drawn
+    // with # and .
+    pub fn read_code(&self) -> String {
+        let grid = self.to_grid();
+        ocr::recognise(&grid).unwrap_or_else(|| ocr::render(&grid))
     }
-    all.into_keys().collect()
 }
 
-fn parse_point(s: &&str) -> CoOrd {
-    let parts: Vec<&str> = s.split(",").collect();
-    let x = parts[0].parse().unwrap();
-    let y = parts[1].parse().unwrap();
-    CoOrd { x, y }
+struct PuzzleData {
+    paper: Paper,
+    folds: Vec<Fold>,
 }
 
-fn parse_fold(s: &&str) -> Fold {
-    let parts: Vec<&str> = s.split("=").collect();
-    // need to remove "fold along " to get the single character afterwards
-    let axis = match &parts[0][11..] {
-        "x" => Axis::X,
-        "y" => Axis::Y,
-        c => panic!("unexpected axis character: {}", c),
+fn parse_point(input: Input) -> ParseResult<Point2> {
+    let (x, input) = unsigned(input)?;
+    let (_, input) = literal(",")(input)?;
+    let (y, input) = unsigned(input)?;
+    Ok((Point2 { x, y }, input))
+}
+
+fn parse_fold(input: Input) -> ParseResult<Fold> {
+    let (_, input) = literal("fold along ")(input)?;
+    let (axis, input) = keyword(&[("x", Axis::X), ("y", Axis::Y)])(input)?;
+    let (_, input) = literal("=")(input)?;
+    let (position, input) = unsigned(input)?;
+    Ok((Fold { axis, position }, input))
+}
+
+fn parse_puzzle(input: Input) -> ParseResult<PuzzleData> {
+    let (points, input) = section(lines(parse_point))(input)?;
+    let (folds, input) = section(lines(parse_fold))(input)?;
+    let puzzle = PuzzleData {
+        paper: Paper::new(&points),
+        folds,
     };
-    let position = parts[1].parse().unwrap();
-    Fold { axis, position }
+    Ok((puzzle, input))
 }
 
 fn read_file() -> PuzzleData {
     let mut file = File::open("./input/input13.txt").unwrap();
     let mut contents = String::new();
     file.read_to_string(&mut contents).unwrap();
-
-    let parts: Vec<Vec<&str>> = contents
-        .lines()
-        .group_by(|s| s.is_empty())
-        .into_iter()
-        .filter(|(k, _)| !k)
-        .map(|(_, g)| g.collect())
-        .collect();
-
-    let points = parts[0].iter().map(parse_point).collect();
-    let folds = parts[1].iter().map(parse_fold).collect();
-
-    PuzzleData { points, folds }
+    parse_all(&contents, parse_puzzle).unwrap()
 }
 
-fn solve_part_1(data: PuzzleData) -> usize {
-    let folded = data.do_fold(data.folds[0]);
-    get_uniques(&folded).len()
+fn solve_part_1(mut data: PuzzleData) -> usize {
+    data.paper.fold(data.folds[0]).unwrap();
+    data.paper.num_dots()
 }
 
 pub fn part_1() -> usize {
@@ -148,8 +173,8 @@ pub fn part_1() -> usize {
     solve_part_1(data)
 }
 
-pub fn part_2() {
+pub fn part_2() -> String {
     let mut data = read_file();
-    data.complete_fold();
-    data.do_print();
+    data.paper.fold_all(&data.folds).unwrap();
+    data.paper.read_code()
 }
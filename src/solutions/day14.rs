@@ -1,12 +1,15 @@
+use super::matrix::{Arithmetic, Exact, Matrix};
+use super::parse::{character, lines, literal, map, parse_all, section, word, Input, ParseResult};
+use num_bigint::BigUint;
 use std::collections::HashMap;
 use std::fmt;
 use std::fs::File;
 use std::io::prelude::*;
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
-struct CharPair {
-    char1: char,
-    char2: char,
+pub struct CharPair {
+    pub char1: char,
+    pub char2: char,
 }
 
 // for debugging
@@ -17,14 +20,14 @@ impl fmt::Debug for CharPair {
 }
 
 impl CharPair {
-    fn new(char1: char, char2: char) -> CharPair {
+    pub fn new(char1: char, char2: char) -> CharPair {
         CharPair { char1, char2 }
     }
 }
 
-struct Rule {
-    input: CharPair,
-    output: char,
+pub struct Rule {
+    pub input: CharPair,
+    pub output: char,
 }
 
 // for debugging
@@ -39,151 +42,178 @@ struct Polymer {
     rules: Vec<Rule>,
 }
 
-#[derive(Debug)]
-struct PolymerContents {
-    contents: HashMap<CharPair, usize>,
-    first_pair: CharPair,
-    last_pair: CharPair,
+// works with counts of each adjacent pair of elements rather than the polymer itself, which
+// grows exponentially. One step of insertion is a linear map on the pair counts, so we can
+// jump any number of steps ahead by raising its matrix to a power
+pub struct InsertionEngine {
+    elements: Vec<char>,
+    // indexed by pair index (see pair_index), giving the index of the element inserted
+    // between that pair, if there is a rule for it
+    rules: Vec<Option<usize>>,
+    template: Vec<usize>,
 }
 
-impl Polymer {
-    fn get_contents(&self) -> PolymerContents {
-        PolymerContents::new(&self.template)
-    }
-}
-
-impl PolymerContents {
-    fn new(s: &str) -> PolymerContents {
-        let mut contents = HashMap::new();
-        let mut first_pair = None;
-        let mut last_pair = None;
-        let total_chars = s.len();
-        let mut current_index = 0;
-        for (char1, char2) in s.chars().zip(s.chars().skip(1)) {
-            let pair = CharPair::new(char1, char2);
-            if current_index == 0 {
-                first_pair = Some(pair);
-            }
-            if current_index == total_chars - 2 {
-                last_pair = Some(pair);
-            }
-            let value = contents.entry(pair).or_insert(0);
-            *value += 1;
-            current_index += 1;
+impl InsertionEngine {
+    // fails if the template is empty or there are two different rules for the same pair
+    pub fn new(template: &str, rules: &[Rule]) -> Result<InsertionEngine, String> {
+        if template.is_empty() {
+            return Err("the polymer template is empty".to_owned());
         }
-        let first_pair = first_pair.unwrap();
-        let last_pair = last_pair.unwrap();
-        PolymerContents {
-            contents,
-            first_pair,
-            last_pair,
+        let mut elements: Vec<char> = template.chars().collect();
+        for rule in rules {
+            elements.extend([rule.input.char1, rule.input.char2, rule.output]);
         }
+        elements.sort_unstable();
+        elements.dedup();
+
+        let index = |c: char| elements.binary_search(&c).unwrap();
+        let num_elements = elements.len();
+        let mut rule_table = vec![None; num_elements * num_elements];
+        for rule in rules {
+            let CharPair { char1, char2 } = rule.input;
+            let entry = &mut rule_table[index(char1) * num_elements + index(char2)];
+            match entry {
+                Some(existing) if *existing != index(rule.output) => {
+                    return Err(format!(
+                        "more than one rule given for pair {:?}",
+                        rule.input
+                    ));
+                }
+                _ => *entry = Some(index(rule.output)),
+            }
+        }
+        let template = template.chars().map(index).collect();
+
+        Ok(InsertionEngine {
+            elements,
+            rules: rule_table,
+            template,
+        })
     }
 
-    fn apply_rules(&mut self, rules: &Vec<Rule>) {
-        let mut new_contents = HashMap::new();
-        let mut new_first = self.first_pair;
-        let mut new_last = self.last_pair;
-        for (&pair, old_count) in &self.contents {
-            let mut applied_rule = false;
-            for rule in rules {
-                if rule.input == pair {
-                    let new_pair_1 = CharPair::new(pair.char1, rule.output);
-                    let count = new_contents.entry(new_pair_1).or_insert(0);
-                    *count += old_count;
-                    if pair == self.first_pair {
-                        new_first = new_pair_1;
-                    }
+    fn num_pairs(&self) -> usize {
+        self.elements.len() * self.elements.len()
+    }
 
-                    let new_pair_2 = CharPair::new(rule.output, pair.char2);
-                    let count = new_contents.entry(new_pair_2).or_insert(0);
-                    *count += old_count;
-                    if pair == self.last_pair {
-                        new_last = new_pair_2;
-                    }
+    fn pair_index(&self, first: usize, second: usize) -> usize {
+        first * self.elements.len() + second
+    }
 
-                    applied_rule = true;
-                    break;
+    // the entry in row i, column j is the number of pairs of type i produced by
+    // one pair of type j in a single step
+    fn step_matrix<A: Arithmetic>(&self, arith: &A) -> Matrix<A::Value> {
+        let mut matrix = Matrix::zero(self.num_pairs(), arith);
+        for first in 0..self.elements.len() {
+            for second in 0..self.elements.len() {
+                let pair = self.pair_index(first, second);
+                match self.rules[pair] {
+                    Some(inserted) => {
+                        let left = self.pair_index(first, inserted);
+                        let right = self.pair_index(inserted, second);
+                        // if both new pairs are the same, that pair is produced twice
+                        let left_count = arith.add(matrix.get(left, pair), &arith.one());
+                        matrix.set(left, pair, left_count);
+                        let right_count = arith.add(matrix.get(right, pair), &arith.one());
+                        matrix.set(right, pair, right_count);
+                    }
+                    None => matrix.set(pair, pair, arith.one()),
                 }
             }
-            if !applied_rule {
-                let count = new_contents.entry(pair).or_insert(0);
-                *count += 1;
-            }
         }
-        self.contents = new_contents;
-        self.first_pair = new_first;
-        self.last_pair = new_last;
+        matrix
     }
 
-    fn apply_rules_times(&mut self, rules: &Vec<Rule>, times: usize) {
-        for _ in 0..times {
-            self.apply_rules(rules);
+    fn initial_pairs<A: Arithmetic>(&self, arith: &A) -> Vec<A::Value> {
+        let mut counts = vec![0; self.num_pairs()];
+        for (&first, &second) in self.template.iter().zip(self.template.iter().skip(1)) {
+            counts[self.pair_index(first, second)] += 1;
         }
+        counts
+            .into_iter()
+            .map(|count| arith.number(count))
+            .collect()
     }
 
-    fn count_chars(&self) -> HashMap<char, usize> {
-        let mut counts = HashMap::new();
-        for (pair, count) in &self.contents {
-            let CharPair { char1, char2 } = pair;
-            let current_count_1 = counts.entry(*char1).or_insert(0);
-            *current_count_1 += count;
-            // note that we do NOT usually count the second character,
-            // or that will be consistently double-counted.
-            // To get the counts right, we must just add 1 for the second character of the LAST pair
-            if pair == &self.last_pair {
-                let current_count_2 = counts.entry(*char2).or_insert(0);
-                *current_count_2 += 1;
-            }
+    pub fn pair_counts<A: Arithmetic>(&self, steps: u64, arith: &A) -> Vec<A::Value> {
+        let matrix = self.step_matrix(arith).pow(steps, arith);
+        matrix.apply(&self.initial_pairs(arith), arith)
+    }
+
+    // the number of each element in the polymer after the given number of steps. Every element
+    // is the first of exactly one pair, apart from the last one - which is always the last
+    // element of the template, as insertions only ever happen between two elements
+    pub fn element_counts<A: Arithmetic>(&self, steps: u64, arith: &A) -> HashMap<char, A::Value> {
+        let pairs = self.pair_counts(steps, arith);
+        let mut counts: Vec<A::Value> = vec![arith.zero(); self.elements.len()];
+        for (pair, count) in pairs.iter().enumerate() {
+            let first = pair / self.elements.len();
+            counts[first] = arith.add(&counts[first], count);
         }
-        counts
+        let last = *self.template.last().unwrap();
+        counts[last] = arith.add(&counts[last], &arith.one());
+        self.elements.iter().copied().zip(counts).collect()
+    }
+
+    // the difference between the most and least common element, as asked for in the puzzle
+    pub fn difference(&self, steps: u64) -> BigUint {
+        let counts = self.element_counts(steps, &Exact);
+        // elements which only appear in rules that never fire don't count
+        let present: Vec<&BigUint> = counts.values().filter(|count| count.bits() > 0).collect();
+        let max = present.iter().max().unwrap();
+        let min = present.iter().min().unwrap();
+        *max - *min
     }
+}
 
-    fn get_difference(&self) -> usize {
-        let counts = self.count_chars();
-        let max = counts.values().max().unwrap();
-        let min = counts.values().min().unwrap();
-        max - min
+impl Polymer {
+    fn get_engine(&self) -> InsertionEngine {
+        InsertionEngine::new(&self.template, &self.rules).unwrap()
     }
 }
 
-fn parse_rule(s: &str) -> Rule {
-    let parts: Vec<&str> = s.split(" -> ").collect();
-    let output = parts[1].chars().nth(0).unwrap();
-    let first_part: Vec<char> = parts[0].chars().collect();
-    let char1 = first_part[0];
-    let char2 = first_part[1];
-    let input = CharPair { char1, char2 };
-    Rule { input, output }
+fn parse_element(input: Input) -> ParseResult<char> {
+    character("an element", |c| c.is_ascii_alphabetic())(input)
+}
+
+fn parse_rule(input: Input) -> ParseResult<Rule> {
+    let (char1, input) = parse_element(input)?;
+    let (char2, input) = parse_element(input)?;
+    let (_, input) = literal(" -> ")(input)?;
+    let (output, input) = parse_element(input)?;
+    let rule = Rule {
+        input: CharPair::new(char1, char2),
+        output,
+    };
+    Ok((rule, input))
+}
+
+fn parse_polymer(input: Input) -> ParseResult<Polymer> {
+    let (template, input) = section(map(word, str::to_owned))(input)?;
+    let (rules, input) = section(lines(parse_rule))(input)?;
+    Ok((Polymer { template, rules }, input))
 }
 
 fn read_file() -> Polymer {
     let mut file = File::open("./input/input14.txt").unwrap();
     let mut contents = String::new();
     file.read_to_string(&mut contents).unwrap();
-    let template = contents.lines().nth(0).unwrap().to_owned();
-    let rules = contents.lines().skip(2).map(parse_rule).collect();
-    Polymer { template, rules }
+    parse_all(&contents, parse_polymer).unwrap()
 }
 
-fn solve_part_1(polymer: &mut Polymer) -> usize {
-    let mut contents = polymer.get_contents();
-    contents.apply_rules_times(&polymer.rules, 10);
-    contents.get_difference()
+fn solve_part_1(polymer: &Polymer) -> BigUint {
+    polymer.get_engine().difference(10)
 }
 
-fn solve_part_2(polymer: &mut Polymer) -> usize {
-    let mut contents = polymer.get_contents();
-    contents.apply_rules_times(&polymer.rules, 40);
-    contents.get_difference()
+fn solve_part_2(polymer: &Polymer) -> BigUint {
+    polymer.get_engine().difference(40)
 }
 
-pub fn part_1() -> usize {
-    let mut polymer = read_file();
-    solve_part_1(&mut polymer)
+pub fn part_1() -> BigUint {
+    let polymer = read_file();
+    solve_part_1(&polymer)
 }
 
-pub fn part_2() -> usize {
-    let mut polymer = read_file();
-    solve_part_2(&mut polymer)
+pub fn part_2() -> BigUint {
+    let polymer = read_file();
+    solve_part_2(&polymer)
 }
@@ -1,171 +1,59 @@
-use std::collections::HashMap;
+use super::parse::{digit, grid, parse_all};
+use super::search::{self, Graph};
 use std::fs::File;
 use std::io::prelude::*;
 
-// attempting to write my own implementation of Djikstra's algorithm. This has been through many improvements
-// but my solution still takes around 45 seconds for part 2, even when compiled in release mode.
-// It must be possible to do better, but I'm no longer too inclined to squeeze more performance out of this!
-
-#[derive(Clone, Debug)]
-struct Node {
-    value: u8,
-    visited: bool,
-    connected: Vec<(usize, usize)>,
-    distance_to: Option<usize>,
-    row: usize,
-    col: usize,
-}
-
-impl Node {
-    fn new(nums: &Vec<Vec<u8>>, row: usize, col: usize) -> Node {
-        let value = nums[row][col];
-        let height = nums.len();
-        let width = nums[0].len();
-        let mut neighbours = vec![];
-        if row > 0 {
-            neighbours.push((row - 1, col));
-        }
-        if row < height - 1 {
-            neighbours.push((row + 1, col));
-        }
-        if col > 0 {
-            neighbours.push((row, col - 1));
-        }
-        if col < width - 1 {
-            neighbours.push((row, col + 1));
-        }
-
-        Node {
-            value,
-            visited: false,
-            connected: neighbours,
-            distance_to: None,
-            row,
-            col,
-        }
-    }
-}
-
-struct Djikstra {
-    nodes: HashMap<(usize, usize), Node>,
-    priority_list: Vec<(usize, usize)>,
-    width: usize,
+struct RiskMap {
+    risks: Vec<Vec<u8>>,
     height: usize,
+    width: usize,
 }
 
-impl Djikstra {
-    fn new(nums: Vec<Vec<u8>>) -> Djikstra {
-        let height = nums.len();
-        let width = nums[0].len();
-        let mut nodes = HashMap::new();
-        let priority_list = vec![];
-        for row in 0..height {
-            for col in 0..width {
-                let node = Node::new(&nums, row, col);
-                nodes.insert((row, col), node);
-            }
-        }
-        // starting with empty priority list so we don't have an enormous array to start with.
-        // Nodes will be inserted as they are needed (see update_queue method)
-        Djikstra {
-            nodes,
-            priority_list,
+impl RiskMap {
+    fn new(risks: Vec<Vec<u8>>) -> RiskMap {
+        let height = risks.len();
+        let width = risks[0].len();
+        RiskMap {
+            risks,
             height,
             width,
         }
     }
 
-    fn get_node(&self, row: &usize, col: &usize) -> Node {
-        self.nodes.get(&(*row, *col)).unwrap().clone()
-    }
-
-    // gets the next node AND removes it from the priority list.
-    // (This is more efficient, to keep the list small - the node won't be needed again afterward)
-    fn get_next_node(&mut self) -> Node {
-        let mut found_node = None;
-        for (row, col) in &self.priority_list {
-            let node = self.get_node(row, col);
-            if !node.visited {
-                found_node = Some(node);
-                break;
-            }
-        }
-        let actual_node = found_node.unwrap();
-        self.priority_list = self
-            .priority_list
-            .iter()
-            .filter(|(row, col)| row != &actual_node.row || col != &actual_node.col)
-            .map(|&pair| pair)
-            .collect();
-        actual_node
+    // the least total risk of any path from the top left to the bottom right. Every step costs at
+    // least 1 and takes at most 1 off the number of steps left, so that number is a consistent
+    // heuristic for A*
+    fn lowest_total_risk(&self) -> usize {
+        let destination = (self.height - 1, self.width - 1);
+        let distance_left =
+            |&(row, col): &(usize, usize)| (destination.0 - row) + (destination.1 - col);
+        search::a_star(self, (0, 0), |&node| node == destination, distance_left)
+            .unwrap()
+            .cost
     }
+}
 
-    // updates queue by simply re-adding the (row, col) pair at the now-correct point.
-    // It DOES NOT remove anything - firstly it does not need to (the newer one has lower priority so
-    // is inserted earlier, so will always be find first), secondly the performance-killing memory issue
-    // of carrying around an ever-growing array of over 10,000 elements is removed (in the get_next_node
-    // method) by removing an item from the queue as soon as it has been used.
-    fn update_queue(&mut self, row: &usize, col: &usize, new_distance: usize) {
-        let mut index = 0;
-        let insert_index = loop {
-            if index >= self.priority_list.len() {
-                // means we haven't found the node yet. So let's insert it!
-                self.priority_list.push((*row, *col));
-                return;
-            }
-            let (old_row, old_col) = self.priority_list[index];
-            let distance = self.get_node(&old_row, &old_col).distance_to;
-            if distance.is_none() || distance.unwrap() > new_distance {
-                // node found here
-                break index;
-            }
-            index += 1;
-        };
-        // insert the found note into the list at the appropriate index
-        self.priority_list.insert(insert_index, (*row, *col));
-    }
+impl Graph for RiskMap {
+    type Node = (usize, usize);
 
-    fn do_step(&mut self, destination: (usize, usize)) -> Option<usize> {
-        let mut node = self.get_next_node();
-        for (row, col) in &node.connected {
-            let mut connected_node = self.get_node(&row, &col);
-            if !connected_node.visited {
-                let new_val = node.distance_to.unwrap() + connected_node.value as usize;
-                let update_distance = match connected_node.distance_to {
-                    Some(old_distance) => new_val < old_distance,
-                    None => true,
-                };
-                if update_distance {
-                    connected_node.distance_to = Some(new_val);
-                    self.nodes.insert(
-                        (connected_node.row, connected_node.col),
-                        connected_node.clone(),
-                    );
-                    self.update_queue(row, col, new_val);
-                }
-            }
+    fn successors(&self, &(row, col): &(usize, usize)) -> Vec<((usize, usize), usize)> {
+        let mut neighbours = vec![];
+        if row > 0 {
+            neighbours.push((row - 1, col));
         }
-        node.visited = true;
-        self.nodes.insert((node.row, node.col), node.clone());
-        // check if node is destination. If so, return its distance_to
-        if (node.row, node.col) == destination {
-            node.distance_to
-        } else {
-            None
+        if row < self.height - 1 {
+            neighbours.push((row + 1, col));
         }
-    }
-
-    fn solve(&mut self, start: (usize, usize), destination: (usize, usize)) -> usize {
-        let (start_row, start_col) = start;
-        let mut starting_node = self.get_node(&start_row, &start_col);
-        starting_node.distance_to = Some(0);
-        self.nodes.insert(start, starting_node);
-        self.priority_list.insert(0, (start_row, start_col));
-        let mut res = None;
-        while res.is_none() {
-            res = self.do_step(destination);
+        if col > 0 {
+            neighbours.push((row, col - 1));
+        }
+        if col < self.width - 1 {
+            neighbours.push((row, col + 1));
         }
-        res.unwrap()
+        neighbours
+            .into_iter()
+            .map(|(row, col)| ((row, col), self.risks[row][col] as usize))
+            .collect()
     }
 }
 
@@ -173,26 +61,17 @@ fn read_file() -> Vec<Vec<u8>> {
     let mut file = File::open("./input/input15.txt").unwrap();
     let mut contents = String::new();
     file.read_to_string(&mut contents).unwrap();
-    contents
-        .lines()
-        .map(|line| {
-            line.chars()
-                .map(|digit| digit.to_string().parse().unwrap())
-                .collect()
-        })
-        .collect()
+    parse_all(&contents, grid("a digit", digit)).unwrap()
 }
 
-fn solve_part_1(mut nodes: Djikstra) -> usize {
-    let start = (0, 0);
-    let destination = (nodes.height - 1, nodes.width - 1);
-    nodes.solve(start, destination)
+fn solve_part_1(map: RiskMap) -> usize {
+    map.lowest_total_risk()
 }
 
 pub fn part_1() -> usize {
     let nums = read_file();
-    let nodes = Djikstra::new(nums);
-    solve_part_1(nodes)
+    let map = RiskMap::new(nums);
+    solve_part_1(map)
 }
 
 fn increment(num: u8) -> u8 {
@@ -229,6 +108,6 @@ pub fn part_2() -> usize {
             more_nums.push(new_row);
         }
     }
-    let nodes = Djikstra::new(more_nums);
-    solve_part_1(nodes)
+    let map = RiskMap::new(more_nums);
+    solve_part_1(map)
 }
@@ -1,203 +1,290 @@
+use super::parse::{literal, parse_all, range, Input, ParseResult};
+use std::collections::{BTreeSet, HashSet};
 use std::fs::File;
 use std::io::prelude::*;
 
-#[derive(Clone, Copy)]
-struct Area {
-    x_min: isize,
-    x_max: isize,
-    y_min: isize,
-    y_max: isize,
+#[derive(Clone, Copy, Debug)]
+pub struct Area {
+    pub x_min: isize,
+    pub x_max: isize,
+    pub y_min: isize,
+    pub y_max: isize,
 }
 
-fn read_file() -> Area {
-    let mut file = File::open("./input/input17.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-    let relevant: &Vec<&str> = &contents[13..].split(", ").collect(); // chop off "target area: " which is 13 chars
-    let x_part = relevant[0];
-    let y_part = relevant[1];
-    let x_ends: Vec<&str> = x_part[2..].split("..").collect();
-    let x_min = x_ends[0].parse().unwrap();
-    let x_max = x_ends[1].parse().unwrap();
-    let y_ends: Vec<&str> = y_part[2..].split("..").collect();
-    let y_min = y_ends[0].parse().unwrap();
-    let y_max = y_ends[1].parse().unwrap();
-    Area {
+impl Area {
+    fn contains_x(&self, x: isize) -> bool {
+        self.x_min <= x && x <= self.x_max
+    }
+
+    fn contains_y(&self, y: isize) -> bool {
+        self.y_min <= y && y <= self.y_max
+    }
+
+    pub fn contains(&self, (x, y): (isize, isize)) -> bool {
+        self.contains_x(x) && self.contains_y(y)
+    }
+}
+
+fn parse_area(input: Input) -> ParseResult<Area> {
+    let (_, input) = literal("target area: x=")(input)?;
+    let ((x_min, x_max), input) = range(input)?;
+    let (_, input) = literal(", y=")(input)?;
+    let ((y_min, y_max), input) = range(input)?;
+    let area = Area {
         x_min,
         x_max,
         y_min,
         y_max,
-    }
+    };
+    Ok((area, input))
+}
+
+fn read_file() -> Area {
+    let mut file = File::open("./input/input17.txt").unwrap();
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).unwrap();
+    parse_all(&contents, parse_area).unwrap()
 }
 
 /*
-Need to use reasoning to figure out what code to write! (for part 1, this is)
-NOTE: after initially trying to adapt this for part 2 (before giving up and just brute forcing), I came
-to have some doubts as to whether this is 100% accurate, particularly for the checks on v_x. It's so easy
-to make silly mistakes! But it still gave the correct answer in practice, so I'm not too worried...
-
-Let the starting velocity be (v_x, v_y). We can clearly assume both v_x and v_y are positive.
-(If v_x is negative the target can never be hit, and if v_y is negative the max height will be 0 which
-is absurd assuming there is any way at all to hit the target from a starting velocity with positive y component.)
-Then the x values at successive steps will be:
-v_x
-v_x + (v_x - 1)
-...
-v_x + (v_x - 1) + ... + 2 + 1,
-after which the x coordinate will never change again (as the x velocity has decreased to 0 and will stay there
-forever after).
-(This last value is well known to be given by the formula v_x * (v_x + 1) / 2.)
-Meanwhile, the y co-ordinate is doing the same thing at each step, with v_x replaced by v_y.
-Except it does keep moving afterwards, changing from going up to going down. Thus the highest point is going
-to be v_y * (v_y + 1) / 2, so v_y is all we have to work out.
-
-The only constraint is that at at least one of these points on the trajectory must fall inside the target area.
-But in practice (in both the example given and in the real data), the y values in the target area are all negative.
-But all the y values corresponding to the x values above are clearly positive. They will only go negative when
-the change in y has already reached below 0 - and in fact gone down to -v_y. At that point - after 2*v_y + 1 steps -
-the y position will finally be back to 0. Only after that will it go through negative numbers, of the form:
--v_y - 1
--2*v_y - 3
--3*v_y - 6
-
-That already restricts v_y quite a bit. In particular, v_y can be AT MOST (-y_min) - 1 (if bigger, -v_y - 1 is
-below y_min and hence all the numbers above will be - ie the y co-ord can never be in range.
-
-And anything smaller clearly works.
-
-For such a v_y, we can choose any i=1,2... provided that the value -(i*v_y + i*(i+1)/2) is in the y-range.
-
-The above y position, for a given i, occurs at step 2*v_y + i + 1. At this point, the x-coordinate will be
-- if 2*v_y + i + 1 >= v_x: v_x * (v_x + 1) / 2 (call this case 1)
-- if 2*v_y + i + 1 < v_x: (2 * v_y + i + 1) + (2 * v_y + i + 2) + ... + v_x (case 2)
-In case 1, the x co-ordinate will already have stabilised at its max. In case 2 it will still be "on the way".
-
-So the most general procedure will be as follows:
-- start with v_y = -(1 + y_min) and work down from there to 1. Test each v_y as follows:
-- find all i such that -(i*v_y + i*(i+1)/2) - or equivalently -(i*v_y + 1 + 2 + ... + i) - is in the y range.
-(This is easily done, start with i = 1 and continue until the expression falls below the y_min. The expression
-obviously decreases as i gets bigger, since all variables are positive)
-- for each such i, compute the value of 2*v_y + i. We now test possible v_x values as follows:
-- for v_x = 1,..,2*v_y + i, compute the sum 1 + ... + v_x (equal to v_x * (v_x + 1) / 2). If any of those falls
-in the x range, we are done - this value of i works, so this v_y is valid.
-- if not, now test larger values of v_x for whether (2*v_y + i + 1) + ... + v_x is in x range or not. This process
-cannot go on forever because this expression keeps on increasing (remember that v_y is fixed at this point), so
-eventually it will overshoot the range, and if this happens without finding anything within the range, we can
-stop and test the next v_y (the current v_y, decremented by 1).
-
-An example of how this works with the given example:
-
-- y_min is -10, so our initial test is for v_y = 9. (Which we know in advance will be successful!)
-- we need all i > 1 for which -(i * 9 + 1 + 2 + ... + i) is in the range -10 to -5, inclusive.
-Clearly this can be trivially programmed, and here comes out as only i = 1 possible. (I think this will always
-be the onlly possibility for the maximum theoretically possible v_y.)
-- we now test v_x, first for values up to and including 2*v_y + 1, or 19:
-- for such values we need the sum 1 + ... + v_x to be in the x range - here 20..30. Clearly 6 and 7 both work,
-while still being <= 19.
-So we are done very early in this case, and v_y = 9 is confirmed as correct.
-
-Thus the answer is 1 + 2 + ... + 9 = 45.
+The two axes are independent apart from having to be in the target on the same step, so we work
+out separately which steps each possible starting velocity is in range on for each axis, and then
+match them up.
+
+Write T(n) for 1 + 2 + ... + n = n * (n + 1) / 2.
+
+The x axis: with starting velocity v_x > 0, the position after n steps is
+n * v_x - T(n - 1) while n < v_x, after which the probe has stopped at T(v_x) for good. (And
+negative v_x is the mirror image of this.) For n >= 1 the probe is always at least |v_x| away, so
+only |v_x| up to the furthest edge of the target can ever work - a finite number to check.
+But if the probe stops inside the target's x range, it's in range for every step from then on.
+
+The y axis: with starting velocity v_y the position after n steps is n * v_y - T(n - 1), with no
+stopping. Doubling this, 2 * y = n * (2 * v_y - n + 1) - so for the probe to be at a non-zero
+height y after n steps, n has to divide 2 * y. So if the target doesn't include height 0,
+n can be at most twice the furthest its edges are from 0. And for each n, the position is
+increasing in v_y, so the v_y that work form a window from
+(y_min + T(n - 1)) / n, rounded up, to (y_max + T(n - 1)) / n, rounded down.
+
+If the target does include height 0, any v_y >= 0 gets back to 0 on step 2 * v_y + 1. So if
+some v_x also stops in the target's x range, there are infinitely many velocities that work.
+Otherwise, every v_x only works on steps before it stops, which is less than the largest |v_x|
+we check, so that bounds n instead.
 */
 
 // trivial computation but used quite a lot!
-fn total_up_to(n: usize) -> usize {
+fn total_up_to(n: isize) -> isize {
     (n * (n + 1)) / 2
 }
 
-fn get_max_starting_y(target: Area) -> usize {
-    let Area {
-        x_min,
-        x_max,
-        y_min,
-        y_max,
-    } = target;
-
-    let mut max_starting_y = -(y_min + 1);
-    while max_starting_y > 0 {
-        let mut i = 1;
-        let mut test = -(i * max_starting_y + total_up_to(i as usize) as isize);
-        while test >= y_min {
-            if test <= y_max {
-                for starting_x in 1..=(2 * max_starting_y + i + 1) {
-                    let end_x = total_up_to(starting_x as usize) as isize;
-                    if end_x >= x_min && end_x <= x_max {
-                        return max_starting_y as usize;
-                    }
-                }
-                let mut starting_x = 2 * max_starting_y + i + 2;
-                let mut x_test = total_up_to(starting_x as usize) as isize
-                    - total_up_to(2 * max_starting_y as usize + i as usize) as isize;
-                while x_test <= x_max {
-                    if x_test >= x_min {
-                        return max_starting_y as usize;
-                    }
-                    starting_x += 1;
-                    x_test = total_up_to(starting_x as usize) as isize
-                        - total_up_to(2 * max_starting_y as usize + i as usize) as isize;
-                }
+fn x_after(v_x: isize, steps: isize) -> isize {
+    let speed = v_x.abs();
+    let distance = if steps >= speed {
+        total_up_to(speed)
+    } else {
+        steps * speed - total_up_to(steps - 1)
+    };
+    distance * v_x.signum()
+}
+
+// the steps on which a starting x velocity is in the target's x range: all those up to the
+// given limit which are before the probe stops, along with the step it stops on if it stops in
+// range (in which case it's in range on every step after that too)
+fn x_steps(target: Area, v_x: isize, limit: isize) -> (Vec<isize>, Option<isize>) {
+    let stops_at = v_x.abs().max(1);
+    let moving = (1..stops_at.min(limit + 1))
+        .filter(|&steps| target.contains_x(x_after(v_x, steps)))
+        .collect();
+    let stopped = if target.contains_x(x_after(v_x, stops_at)) {
+        Some(stops_at)
+    } else {
+        None
+    };
+    (moving, stopped)
+}
+
+// the lowest and highest starting y velocity that are in the target's y range after the given
+// number of steps
+fn y_window(target: Area, steps: isize) -> (isize, isize) {
+    let offset = total_up_to(steps - 1);
+    let low = (target.y_min + offset).div_euclid(steps)
+        + if (target.y_min + offset).rem_euclid(steps) == 0 {
+            0
+        } else {
+            1
+        };
+    let high = (target.y_max + offset).div_euclid(steps);
+    (low, high)
+}
+
+// every starting velocity which puts the probe in the target area after some number of steps.
+// The target can be anywhere, but fails if there are infinitely many such velocities (or the
+// target is empty)
+pub fn valid_velocities(target: Area) -> Result<Vec<(isize, isize)>, String> {
+    if target.x_min > target.x_max || target.y_min > target.y_max {
+        return Err(format!("{:?} is empty", target));
+    }
+    let max_speed_x = target.x_min.abs().max(target.x_max.abs());
+    let max_steps = if target.contains_y(0) {
+        max_speed_x
+    } else {
+        2 * target.y_min.abs().max(target.y_max.abs())
+    };
+
+    let mut velocities = BTreeSet::new();
+    for v_x in -max_speed_x..=max_speed_x {
+        let (moving, stopped) = x_steps(target, v_x, max_steps);
+        if stopped.is_some() && target.contains_y(0) {
+            return Err(format!(
+                "infinitely many velocities reach {:?}, including ({}, n) for any n >= 0",
+                target, v_x
+            ));
+        }
+        let stopped_steps = stopped.into_iter().flat_map(|from| from..=max_steps);
+        for steps in moving.into_iter().chain(stopped_steps) {
+            let (low, high) = y_window(target, steps);
+            for v_y in low..=high {
+                velocities.insert((v_x, v_y));
             }
-            i += 1;
-            test = -(i * max_starting_y + total_up_to(i as usize) as isize);
         }
-        max_starting_y -= 1;
     }
-    panic!("no possible y value found!");
+    Ok(velocities.into_iter().collect())
 }
 
-fn solve_part_1(target: Area) -> usize {
-    let max_starting = get_max_starting_y(target);
-    total_up_to(max_starting)
+// the highest point reached with a starting y velocity, which is where it started if it
+// starts off going down
+pub fn max_height(v_y: isize) -> isize {
+    if v_y > 0 {
+        total_up_to(v_y)
+    } else {
+        0
+    }
 }
 
-// It's not easy to reuse the code of part 1, as much of the logic behind it relied on the assumption
-// that v_y - the starting y velocity - had to be positive. That is clearly no longer true in finding all
-// paths.
-// So we simpy "brute force" instead.
-// the starting x velocity has to be positive, and clearly can't be more than x_max.
-// the starting y velocity can't be less than y_min, and we already know the max it can be.
-// So we have a finite number of possibilities for x, y and can just follow the path blindly for each.
-
-// helper functio to follow the path and see if we hit the target
-fn hits_target(target: Area, start_x: isize, start_y: isize) -> bool {
-    let Area {
-        x_min,
-        x_max,
-        y_min,
-        y_max,
-    } = target;
-
-    let mut x = 0;
-    let mut y = 0;
-    let mut v_x = start_x;
-    let mut v_y = start_y;
-    while x <= x_max && y >= y_min {
-        if x >= x_min && y <= y_max {
-            return true;
-        }
+// the position of the probe at the start and after each step, up to the first step on which it's
+// either in the target or below it and falling - after which it can never reach the target
+pub fn trajectory(target: Area, velocity: (isize, isize)) -> Vec<(isize, isize)> {
+    let (mut x, mut y) = (0, 0);
+    let (mut v_x, mut v_y) = velocity;
+    let mut positions = vec![(x, y)];
+    while !((target.contains((x, y)) && positions.len() > 1) || (y < target.y_min && v_y < 0)) {
         x += v_x;
         y += v_y;
-        if v_x > 0 {
-            v_x -= 1;
-        }
+        v_x -= v_x.signum();
         v_y -= 1;
+        positions.push((x, y));
     }
-    false
+    positions
 }
 
-fn solve_part_2(target: Area) -> usize {
-    let mut total = 0;
-    let max_starting_y = get_max_starting_y(target) as isize;
-    for x in 1..=target.x_max {
-        for y in target.y_min..=max_starting_y {
-            if hits_target(target, x, y) {
-                total += 1;
-            }
+// the smallest and largest x and y co-ordinates needed to show both the target and the positions
+fn bounds(target: Area, positions: &[(isize, isize)]) -> (isize, isize, isize, isize) {
+    positions.iter().fold(
+        (target.x_min, target.x_max, target.y_min, target.y_max),
+        |(x_min, x_max, y_min, y_max), &(x, y)| {
+            (x_min.min(x), x_max.max(x), y_min.min(y), y_max.max(y))
+        },
+    )
+}
+
+// draws the positions as in the puzzle, with S for the start, # for the probe, T for the target
+// and . for empty space. The top row is the highest point
+pub fn render_text(target: Area, positions: &[(isize, isize)]) -> String {
+    let (x_min, x_max, y_min, y_max) = bounds(target, positions);
+    let visited: HashSet<(isize, isize)> = positions.iter().copied().collect();
+    let mut text = String::new();
+    for y in (y_min..=y_max).rev() {
+        for x in x_min..=x_max {
+            text.push(if (x, y) == (0, 0) {
+                'S'
+            } else if visited.contains(&(x, y)) {
+                '#'
+            } else if target.contains((x, y)) {
+                'T'
+            } else {
+                '.'
+            });
         }
+        text.push('\n');
     }
-    total
+    text
+}
+
+// draws the positions as an SVG image, with each unit taking up `scale` pixels. The target is a
+// shaded rectangle, and the probe's path is a line with a dot at each position - red if it's in
+// the target
+pub fn render_svg(target: Area, positions: &[(isize, isize)], scale: usize) -> String {
+    let (x_min, x_max, y_min, y_max) = bounds(target, positions);
+    let scale = scale as isize;
+    // the image's y axis points down, so everything is measured from the top left corner. Each
+    // position is in the middle of its unit square
+    let to_image = |(x, y): (isize, isize)| ((x - x_min) * scale, (y_max - y) * scale);
+    let centre = |position| {
+        let (x, y) = to_image(position);
+        (x + scale / 2, y + scale / 2)
+    };
+    let width = (x_max - x_min + 1) * scale;
+    let height = (y_max - y_min + 1) * scale;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+        width, height, width, height
+    );
+    svg.push_str(&format!(
+        "<rect width=\"{}\" height=\"{}\" fill=\"white\"/>\n",
+        width, height
+    ));
+    let (target_left, target_top) = to_image((target.x_min, target.y_max));
+    svg.push_str(&format!(
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"lightblue\"/>\n",
+        target_left,
+        target_top,
+        (target.x_max - target.x_min + 1) * scale,
+        (target.y_max - target.y_min + 1) * scale
+    ));
+    let points: Vec<String> = positions
+        .iter()
+        .map(|&position| {
+            let (x, y) = centre(position);
+            format!("{},{}", x, y)
+        })
+        .collect();
+    svg.push_str(&format!(
+        "<polyline points=\"{}\" fill=\"none\" stroke=\"black\"/>\n",
+        points.join(" ")
+    ));
+    for &position in positions {
+        let (x, y) = centre(position);
+        let colour = if target.contains(position) {
+            "red"
+        } else {
+            "black"
+        };
+        svg.push_str(&format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>\n",
+            x,
+            y,
+            (scale / 4).max(1),
+            colour
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn solve_part_1(target: Area) -> isize {
+    valid_velocities(target)
+        .unwrap()
+        .iter()
+        .map(|&(_, v_y)| max_height(v_y))
+        .max()
+        .expect("no possible velocity found!")
+}
+
+fn solve_part_2(target: Area) -> usize {
+    valid_velocities(target).unwrap().len()
 }
 
-pub fn part_1() -> usize {
+pub fn part_1() -> isize {
     let target = read_file();
     solve_part_1(target)
 }
@@ -1,4 +1,4 @@
-use serde_json::Value;
+use super::parse::{lines, literal, map, parse_all, unsigned, Input, ParseResult};
 use std::fmt;
 use std::fs::File;
 use std::io::prelude::*;
@@ -372,34 +372,29 @@ fn add_list(nums: Vec<Snailfish>) -> Snailfish {
     result
 }
 
-fn parse_part(val: &Value) -> SnailfishPart {
-    match val {
-        Value::Number(n) => SnailfishPart::Regular(n.as_u64().unwrap() as usize),
-        Value::Array(_) => SnailfishPart::Pair(Box::new(parse_array(val))),
-        _ => panic!("json value is neither an array nor number"),
+fn parse_part(input: Input) -> ParseResult<SnailfishPart> {
+    if input.rest().starts_with('[') {
+        let (pair, input) = parse_snailfish(input)?;
+        Ok((SnailfishPart::Pair(Box::new(pair)), input))
+    } else {
+        map(unsigned, SnailfishPart::Regular)(input)
     }
 }
 
-fn parse_array(array: &Value) -> Snailfish {
-    if let Value::Array(vec) = array {
-        assert_eq!(vec.len(), 2);
-        let first = parse_part(&vec[0]);
-        let second = parse_part(&vec[1]);
-        return Snailfish { first, second };
-    }
-    panic!("line isn't decodable to an array");
-}
-
-fn read_line(line: &str) -> Snailfish {
-    let json = serde_json::from_str(line).unwrap();
-    parse_array(&json)
+fn parse_snailfish(input: Input) -> ParseResult<Snailfish> {
+    let (_, input) = literal("[")(input)?;
+    let (first, input) = parse_part(input)?;
+    let (_, input) = literal(",")(input)?;
+    let (second, input) = parse_part(input)?;
+    let (_, input) = literal("]")(input)?;
+    Ok((Snailfish { first, second }, input))
 }
 
 fn read_file() -> Vec<Snailfish> {
     let mut file = File::open("./input/input18.txt").unwrap();
     let mut contents = String::new();
     file.read_to_string(&mut contents).unwrap();
-    contents.lines().map(read_line).collect()
+    parse_all(&contents, lines(parse_snailfish)).unwrap()
 }
 
 fn solve_part_1(nums: Vec<Snailfish>) -> usize {
@@ -1,15 +1,11 @@
-use itertools::Itertools;
+use super::geometry::Point3;
+use super::parse::{
+    line, lines, literal, parse_all, sections, signed, unsigned, Input, ParseResult,
+};
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
-struct Point {
-    x: isize,
-    y: isize,
-    z: isize,
-}
-
 #[derive(Clone, Copy, Debug)]
 enum AxesFlipped {
     None,
@@ -96,42 +92,36 @@ impl Rotation {
 
         all
     }
-}
 
-impl Point {
-    fn rotate(&self, r: &Rotation) -> Self {
-        let mut rotated = Point {
-            x: self.x,
-            y: self.y,
-            z: self.z,
-        };
-        match r.permutation {
+    fn apply(&self, point: &Point3) -> Point3 {
+        let mut rotated = *point;
+        match self.permutation {
             Permutation::None => (),
             Permutation::YZX => {
-                rotated.x = self.y;
-                rotated.y = self.z;
-                rotated.z = self.x;
+                rotated.x = point.y;
+                rotated.y = point.z;
+                rotated.z = point.x;
             }
 
             Permutation::ZXY => {
-                rotated.x = self.z;
-                rotated.y = self.x;
-                rotated.z = self.y;
+                rotated.x = point.z;
+                rotated.y = point.x;
+                rotated.z = point.y;
             }
             Permutation::XZY => {
-                rotated.y = self.z;
-                rotated.z = self.y;
+                rotated.y = point.z;
+                rotated.z = point.y;
             }
             Permutation::ZYX => {
-                rotated.x = self.z;
-                rotated.z = self.x;
+                rotated.x = point.z;
+                rotated.z = point.x;
             }
             Permutation::YXZ => {
-                rotated.x = self.y;
-                rotated.y = self.x;
+                rotated.x = point.y;
+                rotated.y = point.x;
             }
         }
-        match r.flipped {
+        match self.flipped {
             AxesFlipped::None => (),
             AxesFlipped::X => {
                 rotated.x *= -1;
@@ -162,43 +152,19 @@ impl Point {
         }
         rotated
     }
-
-    fn rotate_and_translate(
-        &self,
-        r: &Rotation,
-        translate_x: isize,
-        translate_y: isize,
-        translate_z: isize,
-    ) -> Self {
-        let mut rotated = self.rotate(r);
-        rotated.x += translate_x;
-        rotated.y += translate_y;
-        rotated.z += translate_z;
-        rotated
-    }
-
-    fn manhattan(&self, other: &Self) -> usize {
-        ((self.x - other.x).abs() + (self.y - other.y).abs() + (self.z - other.z).abs()) as usize
-    }
 }
 
 // the ID is used to keep track of which scans match with others, to dramatically improve performance
 #[derive(Clone)]
 struct Scan {
     id: usize,
-    points: Vec<Point>,
+    points: Vec<Point3>,
 }
 
 impl Scan {
-    fn rotate_and_translate_all(
-        &mut self,
-        r: &Rotation,
-        translate_x: isize,
-        translate_y: isize,
-        translate_z: isize,
-    ) {
+    fn rotate_and_translate_all(&mut self, r: &Rotation, translation: Point3) {
         for point in &mut self.points {
-            *point = point.rotate_and_translate(r, translate_x, translate_y, translate_z);
+            *point = r.apply(point) + translation;
         }
     }
 
@@ -213,7 +179,7 @@ impl Scan {
         &self,
         other: &Scan,
         target: usize,
-    ) -> Option<(Rotation, isize, isize, isize)> {
+    ) -> Option<(Rotation, Point3)> {
         // This method does the dumb brute force approach to try to find any combination of rotation
         // and translation that puts enough points on the "other" scan on top of this one.
         // Namely, it goes through every point in other, and then tries to put that on each point in
@@ -223,19 +189,11 @@ impl Scan {
         for my_point in &self.points {
             for other_point in &other.points {
                 for rotation in Rotation::all() {
-                    let rotated = my_point.rotate(&rotation);
-                    let translate_x = other_point.x - rotated.x;
-                    let translate_y = other_point.y - rotated.y;
-                    let translate_z = other_point.z - rotated.z;
+                    let translation = *other_point - rotation.apply(my_point);
                     let mut candidate = self.clone();
-                    candidate.rotate_and_translate_all(
-                        &rotation,
-                        translate_x,
-                        translate_y,
-                        translate_z,
-                    );
+                    candidate.rotate_and_translate_all(&rotation, translation);
                     if other.count_overlaps(&candidate) >= target {
-                        return Some((rotation, translate_x, translate_y, translate_z));
+                        return Some((rotation, translation));
                     }
                 }
             }
@@ -244,36 +202,35 @@ impl Scan {
     }
 }
 
-fn parse_points(line: &str) -> Point {
-    let nums: Vec<isize> = line.split(",").map(|num| num.parse().unwrap()).collect();
-    Point {
-        x: nums[0],
-        y: nums[1],
-        z: nums[2],
-    }
+fn parse_point(input: Input) -> ParseResult<Point3> {
+    let (x, input) = signed(input)?;
+    let (_, input) = literal(",")(input)?;
+    let (y, input) = signed(input)?;
+    let (_, input) = literal(",")(input)?;
+    let (z, input) = signed(input)?;
+    Ok((Point3::new(x, y, z), input))
 }
 
-fn parse_scan(lines: Vec<&str>, id: usize) -> Scan {
-    let point_lines = &lines[1..]; // need to chop off --- scanner XXX --- from the start
-    let points = point_lines.iter().map(|&str| parse_points(str)).collect();
-    Scan { points, id }
+fn parse_scan(input: Input) -> ParseResult<Scan> {
+    let header = |input| {
+        let (_, input) = literal("--- scanner ")(input)?;
+        let (id, input) = unsigned(input)?;
+        let (_, input) = literal(" ---")(input)?;
+        Ok((id, input))
+    };
+    let (id, input) = line(header)(input)?;
+    let (points, input) = lines(parse_point)(input)?;
+    Ok((Scan { id, points }, input))
 }
 
 fn read_file() -> Vec<Scan> {
     let mut file = File::open("./input/input19.txt").unwrap();
     let mut contents = String::new();
     file.read_to_string(&mut contents).unwrap();
-    contents
-        .lines()
-        .group_by(|s| s.is_empty())
-        .into_iter()
-        .filter(|(k, _)| !k)
-        .enumerate()
-        .map(|(idx, (_, g))| parse_scan(g.collect(), idx))
-        .collect()
+    parse_all(&contents, sections(parse_scan)).unwrap()
 }
 
-fn all_beacons_and_scanners(scans: Vec<Scan>) -> (Vec<Point>, Vec<Point>) {
+fn all_beacons_and_scanners(scans: Vec<Scan>) -> (Vec<Point3>, Vec<Point3>) {
     // cache for matching
     let mut no_matches = HashMap::new();
     let number = scans.len();
@@ -291,20 +248,12 @@ fn all_beacons_and_scanners(scans: Vec<Scan>) -> (Vec<Point>, Vec<Point>) {
                     continue;
                 }
                 let mut cloned = scan.clone();
-                if let Some((rotation, translate_x, translate_y, translate_z)) =
+                if let Some((rotation, translation)) =
                     cloned.get_transform_for_overlapping_points(&ref_scan, 12)
                 {
-                    scanners.push(Point {
-                        x: translate_x,
-                        y: translate_y,
-                        z: translate_z,
-                    });
-                    cloned.rotate_and_translate_all(
-                        &rotation,
-                        translate_x,
-                        translate_y,
-                        translate_z,
-                    );
+                    // the scanner is at the origin of its own co-ordinates
+                    scanners.push(translation);
+                    cloned.rotate_and_translate_all(&rotation, translation);
                     actual_points.extend_from_slice(&cloned.points);
                     actual_points.sort_unstable();
                     actual_points.dedup();
@@ -324,7 +273,7 @@ fn all_beacons_and_scanners(scans: Vec<Scan>) -> (Vec<Point>, Vec<Point>) {
     (actual_points, scanners)
 }
 
-fn max_manhattan(beacons: Vec<Point>) -> usize {
+fn max_manhattan(beacons: Vec<Point3>) -> usize {
     let mut max = 0;
     for (idx, beacon) in beacons.iter().enumerate() {
         for other_beacon in &beacons[(idx + 1)..] {
@@ -1,103 +1,207 @@
 use std::fs::File;
 use std::io::prelude::*;
 
-#[derive(Copy, Clone)]
-enum Command {
+#[derive(Clone, PartialEq, Debug)]
+pub enum Command {
     Forward(usize),
+    Back(usize),
     Up(usize),
     Down(usize),
+    // anything else of the form "<name> <amount>", for user-defined semantics to make sense of
+    Other(String, usize),
 }
 
-struct Position {
-    horizontal: isize,
-    depth: isize,
+// a course is a list of commands, some of which may be repeated
+#[derive(Clone, PartialEq, Debug)]
+pub enum Instruction {
+    Single(Command),
+    Repeat(usize, Vec<Instruction>),
 }
 
-impl Position {
-    fn new() -> Position {
-        Position {
-            horizontal: 0,
-            depth: 0,
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct State {
+    pub horizontal: isize,
+    pub depth: isize,
+    pub aim: isize,
+}
+
+// how a command changes the submarine's state. Fails if the command isn't understood
+pub trait CommandSemantics {
+    fn apply(&self, state: &mut State, command: &Command) -> Result<(), String>;
+}
+
+// the first reading of the commands, which move the submarine directly
+pub struct Plain;
+
+impl CommandSemantics for Plain {
+    fn apply(&self, state: &mut State, command: &Command) -> Result<(), String> {
+        match *command {
+            Command::Forward(n) => state.horizontal += n as isize,
+            Command::Back(n) => state.horizontal -= n as isize,
+            Command::Up(n) => state.depth -= n as isize,
+            Command::Down(n) => state.depth += n as isize,
+            Command::Other(ref name, _) => return Err(format!("unknown command {}", name)),
         }
+        Ok(())
     }
+}
 
-    fn update(&mut self, command: Command) {
-        match command {
-            Command::Forward(n) => self.horizontal += n as isize,
-            Command::Up(n) => self.depth -= n as isize,
-            Command::Down(n) => self.depth += n as isize,
+// the corrected reading from the manual, where up and down change the aim, and moving goes
+// deeper or shallower depending on it
+pub struct Aimed;
+
+impl CommandSemantics for Aimed {
+    fn apply(&self, state: &mut State, command: &Command) -> Result<(), String> {
+        match *command {
+            Command::Forward(n) => {
+                state.horizontal += n as isize;
+                state.depth += state.aim * n as isize;
+            }
+            // exactly undoes going forward the same amount
+            Command::Back(n) => {
+                state.horizontal -= n as isize;
+                state.depth -= state.aim * n as isize;
+            }
+            Command::Up(n) => state.aim -= n as isize,
+            Command::Down(n) => state.aim += n as isize,
+            Command::Other(ref name, _) => return Err(format!("unknown command {}", name)),
         }
+        Ok(())
     }
 }
 
-fn parse_line(line: &str) -> Command {
+// runs the instructions in order from the given state, calling `observe` with each command and
+// the state after it
+fn execute<S: CommandSemantics>(
+    semantics: &S,
+    instructions: &[Instruction],
+    state: &mut State,
+    observe: &mut dyn FnMut(&Command, State),
+) -> Result<(), String> {
+    for instruction in instructions {
+        match instruction {
+            Instruction::Single(command) => {
+                semantics.apply(state, command)?;
+                observe(command, *state);
+            }
+            Instruction::Repeat(times, body) => {
+                for _ in 0..*times {
+                    execute(semantics, body, state, observe)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// the state at the end of the course, starting from the surface
+pub fn run<S: CommandSemantics>(semantics: &S, course: &[Instruction]) -> Result<State, String> {
+    let mut state = State::default();
+    execute(semantics, course, &mut state, &mut |_, _| ())?;
+    Ok(state)
+}
+
+// every command carried out in the course, with repeats expanded, along with the state after it
+pub fn trace<S: CommandSemantics>(
+    semantics: &S,
+    course: &[Instruction],
+) -> Result<Vec<(Command, State)>, String> {
+    let mut steps = vec![];
+    let mut state = State::default();
+    execute(semantics, course, &mut state, &mut |command, state| {
+        steps.push((command.clone(), state))
+    })?;
+    Ok(steps)
+}
+
+fn parse_command(line: &str) -> Result<Command, String> {
     let parts: Vec<&str> = line.split_ascii_whitespace().collect();
+    if parts.len() != 2 {
+        return Err(format!("expected a command and an amount, found {}", line));
+    }
     let dir = parts[0];
-    let amount: usize = parts[1].parse().unwrap();
-    match dir {
+    let amount: usize = parts[1]
+        .parse()
+        .map_err(|_| format!("invalid amount {}", parts[1]))?;
+    Ok(match dir {
         "forward" => Command::Forward(amount),
+        "back" => Command::Back(amount),
         "up" => Command::Up(amount),
         "down" => Command::Down(amount),
-        _ => panic!("Unexpected command found in input"),
+        _ => Command::Other(dir.to_owned(), amount),
+    })
+}
+
+// parses a course: one command per line, with anything after a # ignored, and blank lines
+// skipped. A block of lines between "repeat <n>" and "end" is done n times, and blocks can be
+// nested. Errors give the line number they were found on
+pub fn parse_course(contents: &str) -> Result<Vec<Instruction>, String> {
+    // the instructions of each block we're currently inside, outermost first, along with how
+    // many times it repeats and the line it started on
+    let mut blocks: Vec<(usize, usize, Vec<Instruction>)> = vec![(1, 0, vec![])];
+    for (index, line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let words: Vec<&str> = line.split_ascii_whitespace().collect();
+        match words[..] {
+            ["repeat", times] => {
+                let times = times
+                    .parse()
+                    .map_err(|_| format!("line {}: invalid repeat count {}", line_number, times))?;
+                blocks.push((times, line_number, vec![]));
+            }
+            ["end"] => {
+                if blocks.len() == 1 {
+                    return Err(format!("line {}: end without repeat", line_number));
+                }
+                let (times, _, body) = blocks.pop().unwrap();
+                let enclosing = &mut blocks.last_mut().unwrap().2;
+                enclosing.push(Instruction::Repeat(times, body));
+            }
+            _ => {
+                let command =
+                    parse_command(line).map_err(|e| format!("line {}: {}", line_number, e))?;
+                blocks
+                    .last_mut()
+                    .unwrap()
+                    .2
+                    .push(Instruction::Single(command));
+            }
+        }
+    }
+    if blocks.len() > 1 {
+        let start = blocks.last().unwrap().1;
+        return Err(format!("line {}: repeat without end", start));
     }
+    Ok(blocks.pop().unwrap().2)
 }
 
-fn read_file() -> Vec<Command> {
+fn read_file() -> Vec<Instruction> {
     let mut file = File::open("./input/input2.txt").unwrap();
     let mut contents = String::new();
     file.read_to_string(&mut contents).unwrap();
-    contents.lines().map(|n| parse_line(n)).collect()
+    parse_course(&contents).unwrap()
 }
 
-fn solve_part_1(commands: Vec<Command>) -> isize {
-    let mut position = Position::new();
-    for &command in commands.iter() {
-        position.update(command);
-    }
+fn solve_part_1(course: Vec<Instruction>) -> isize {
+    let position = run(&Plain, &course).unwrap();
     position.horizontal * position.depth
 }
 
-struct Position2 {
-    horizontal: isize,
-    depth: isize,
-    aim: isize,
-}
-
-impl Position2 {
-    fn new() -> Position2 {
-        Position2 {
-            horizontal: 0,
-            depth: 0,
-            aim: 0,
-        }
-    }
-
-    fn update(&mut self, command: Command) {
-        match command {
-            Command::Forward(n) => {
-                self.horizontal += n as isize;
-                self.depth += self.aim * n as isize;
-            }
-            Command::Up(n) => self.aim -= n as isize,
-            Command::Down(n) => self.aim += n as isize,
-        }
-    }
-}
-
-fn solve_part_2(commands: Vec<Command>) -> isize {
-    let mut position = Position2::new();
-    for &command in commands.iter() {
-        position.update(command);
-    }
+fn solve_part_2(course: Vec<Instruction>) -> isize {
+    let position = run(&Aimed, &course).unwrap();
     position.horizontal * position.depth
 }
 
 pub fn part_1() -> isize {
-    let commands = read_file();
-    solve_part_1(commands)
+    let course = read_file();
+    solve_part_1(course)
 }
 
 pub fn part_2() -> isize {
-    let commands = read_file();
-    solve_part_2(commands)
+    let course = read_file();
+    solve_part_2(course)
 }
@@ -1,156 +1,323 @@
-use std::collections::HashMap;
+use super::parse::{grid, parse_all, section, take_while, try_map, Input, ParseResult};
 use std::fs::File;
+use std::io;
 use std::io::prelude::*;
+use std::path::{Path, PathBuf};
 
-#[derive(Clone, Copy)]
-enum Pixel {
-    On,
-    Off,
-}
+pub struct EnhancementAlgorithm([bool; 512]);
+
+impl EnhancementAlgorithm {
+    pub fn new(pixels: &[bool]) -> Result<EnhancementAlgorithm, String> {
+        let pixels = pixels.try_into().map_err(|_| {
+            format!(
+                "enhancement algorithm has {} pixels rather than 512",
+                pixels.len()
+            )
+        })?;
+        Ok(EnhancementAlgorithm(pixels))
+    }
 
-struct EnhancementAlgorithm(Vec<Pixel>);
+    fn lookup(&self, index: usize) -> bool {
+        self.0[index]
+    }
+}
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
-struct CoOrd {
-    x: isize,
-    y: isize,
+// the image is stored as a dense grid of bits, one row after another, with each row taking up
+// a whole number of 64-bit words.
+// Everything outside the grid - which stretches off to infinity - has the background value.
+#[derive(Clone)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    bits: Vec<u64>,
+    background: bool,
+    // how far the grid has grown on each side since the original image. This means that
+    // position (0, 0) in the grid is (-offset, -offset) in the original image's co-ordinates
+    offset: usize,
 }
 
-impl CoOrd {
-    fn new(x: isize, y: isize) -> CoOrd {
-        CoOrd { x, y }
-    }
-
-    fn get_neighbours(&self) -> Vec<CoOrd> {
-        let CoOrd { x, y } = self;
-        vec![
-            CoOrd::new(x - 1, y - 1),
-            CoOrd::new(*x, y - 1),
-            CoOrd::new(x + 1, y - 1),
-            CoOrd::new(x - 1, *y),
-            CoOrd::new(*x, *y),
-            CoOrd::new(x + 1, *y),
-            CoOrd::new(x - 1, y + 1),
-            CoOrd::new(*x, y + 1),
-            CoOrd::new(x + 1, y + 1),
-        ]
+impl Image {
+    fn blank(width: usize, height: usize, background: bool, offset: usize) -> Image {
+        let words_per_row = width.div_ceil(64);
+        Image {
+            width,
+            height,
+            words_per_row,
+            bits: vec![0; words_per_row * height],
+            background,
+            offset,
+        }
+    }
+
+    // fails if the rows are not all the same length
+    pub fn new(rows: &[Vec<bool>]) -> Result<Image, String> {
+        let width = rows.first().map_or(0, |row| row.len());
+        if rows.iter().any(|row| row.len() != width) {
+            return Err("image rows are not all the same length".to_owned());
+        }
+        let mut image = Image::blank(width, rows.len(), false, 0);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &pixel) in row.iter().enumerate() {
+                if pixel {
+                    image.set(x, y);
+                }
+            }
+        }
+        Ok(image)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn background(&self) -> bool {
+        self.background
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn set(&mut self, x: usize, y: usize) {
+        self.bits[y * self.words_per_row + x / 64] |= 1 << (x % 64);
+    }
+
+    // reads any pixel, including those outside the grid
+    pub fn get(&self, x: isize, y: isize) -> bool {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return self.background;
+        }
+        let (x, y) = (x as usize, y as usize);
+        self.bits[y * self.words_per_row + x / 64] & (1 << (x % 64)) != 0
+    }
+
+    // the number of lit pixels, or None if there are infinitely many
+    pub fn count_lit(&self) -> Option<usize> {
+        if self.background {
+            return None;
+        }
+        Some(
+            self.bits
+                .iter()
+                .map(|word| word.count_ones() as usize)
+                .sum(),
+        )
+    }
+
+    // every pixel can only be affected by those at most 1 away, so the part of the image that
+    // differs from the background grows by 1 on each side per step. Outside that, every pixel
+    // sees 9 background pixels, so the new background is the algorithm's first or last value.
+    // Rather than reading 9 pixels for each new one, we keep the last 3 bits seen in each of
+    // the 3 rows involved, and shift one new bit into each as we move along the row
+    pub fn enhance(&self, algorithm: &EnhancementAlgorithm) -> Image {
+        let background_index = if self.background { 511 } else { 0 };
+        let mut new = Image::blank(
+            self.width + 2,
+            self.height + 2,
+            algorithm.lookup(background_index),
+            self.offset + 1,
+        );
+        let background_window = if self.background { 0b111 } else { 0 };
+        for new_y in 0..new.height {
+            // new pixel (x, y) is centred on old pixel (x - 1, y - 1)
+            let old_y = new_y as isize - 1;
+            let mut windows = [background_window; 3];
+            // the windows start off holding old columns -2 and -1, which are both background
+            for new_x in 0..new.width {
+                let old_x = new_x as isize;
+                let mut index = 0;
+                for (row, window) in windows.iter_mut().enumerate() {
+                    let bit = self.get(old_x, old_y - 1 + row as isize) as usize;
+                    *window = ((*window << 1) | bit) & 0b111;
+                    index = (index << 3) | *window;
+                }
+                if algorithm.lookup(index) {
+                    new.set(new_x, new_y);
+                }
+            }
+        }
+        new
+    }
+
+    // the pixels of the grid along with `margin` extra pixels of background on each side, row
+    // by row. Lit pixels are true
+    fn rows_with_margin(&self, margin: usize) -> Vec<Vec<bool>> {
+        let margin = margin as isize;
+        (-margin..self.height as isize + margin)
+            .map(|y| {
+                (-margin..self.width as isize + margin)
+                    .map(|x| self.get(x, y))
+                    .collect()
+            })
+            .collect()
+    }
+
+    // draws the image as # for lit pixels and . for unlit ones, as in the puzzle
+    pub fn render_text(&self, margin: usize) -> String {
+        let mut text = String::new();
+        for row in self.rows_with_margin(margin) {
+            text.extend(row.into_iter().map(|lit| if lit { '#' } else { '.' }));
+            text.push('\n');
+        }
+        text
+    }
+
+    // writes the image as a binary PBM file, in which lit pixels are black
+    pub fn write_pbm<W: Write>(&self, out: &mut W, margin: usize) -> io::Result<()> {
+        let rows = self.rows_with_margin(margin);
+        let width = self.width + 2 * margin;
+        write!(out, "P4\n{} {}\n", width, rows.len())?;
+        for row in rows {
+            // each row is padded out to a whole number of bytes, most significant bit first
+            let mut bytes = vec![0u8; width.div_ceil(8)];
+            for (x, lit) in row.into_iter().enumerate() {
+                if lit {
+                    bytes[x / 8] |= 0x80 >> (x % 8);
+                }
+            }
+            out.write_all(&bytes)?;
+        }
+        Ok(())
+    }
+
+    // writes the image as a greyscale PNG file, with lit pixels black and unlit ones white
+    pub fn write_png<W: Write>(&self, out: W, margin: usize) -> io::Result<()> {
+        let rows = self.rows_with_margin(margin);
+        let width = self.width + 2 * margin;
+        let mut encoder = png::Encoder::new(out, width as u32, rows.len() as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let data: Vec<u8> = rows
+            .into_iter()
+            .flatten()
+            .map(|lit| if lit { 0 } else { 255 })
+            .collect();
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        writer.write_image_data(&data).map_err(io::Error::other)
     }
 }
 
-struct Image(HashMap<CoOrd, Pixel>);
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FrameFormat {
+    Text,
+    Pbm,
+    Png,
+}
 
-impl Image {
-    fn read_pixel<'a>(&'a self, x: isize, y: isize, default: &'a Pixel) -> &'a Pixel {
-        self.0.get(&CoOrd::new(x, y)).unwrap_or(default)
+impl FrameFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            FrameFormat::Text => "txt",
+            FrameFormat::Pbm => "pbm",
+            FrameFormat::Png => "png",
+        }
     }
 }
 
-struct PuzzleInfo {
+pub struct PuzzleInfo {
     algorithm: EnhancementAlgorithm,
     image: Image,
 }
 
 impl PuzzleInfo {
-    fn new_value(&self, point: CoOrd, default: Pixel) -> Pixel {
-        let neighbours = point.get_neighbours();
-        let mut binary_str = String::new();
-        for CoOrd { x, y } in neighbours {
-            binary_str.push_str(match self.image.read_pixel(x, y, &default) {
-                Pixel::On => "1",
-                Pixel::Off => "0",
-            });
-        }
-        let index = usize::from_str_radix(&binary_str, 2).unwrap();
-        let pixel = self.algorithm.0[index];
-        pixel
-    }
-
-    fn enhance(&mut self, default: Pixel) {
-        let mut new = HashMap::new();
-        for &coord in self.image.0.keys() {
-            let pixel = &self.new_value(coord, default);
-            new.insert(coord, *pixel);
-            for neighbour in coord.get_neighbours() {
-                if let None = new.get(&neighbour) {
-                    let pixel = &self.new_value(neighbour, default);
-                    new.insert(neighbour, *pixel);
-                }
-            }
+    pub fn new(algorithm: EnhancementAlgorithm, image: Image) -> PuzzleInfo {
+        PuzzleInfo { algorithm, image }
+    }
+
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    // the image after the given number of enhancements, leaving this one as it is
+    pub fn image_after(&self, steps: usize) -> Image {
+        let mut image = self.image.clone();
+        for _ in 0..steps {
+            image = image.enhance(&self.algorithm);
         }
-        self.image = Image(new);
+        image
     }
 
-    fn enhance_times(&mut self, times: usize) {
-        let mut default = Pixel::Off;
+    pub fn enhance_times(&mut self, times: usize) {
         for _ in 0..times {
-            // the "twist" is that we can't assume that all "unaccounted for" pixels are off
-            // (by that I mean those pixels all of whose neighbours, as well as themselves, are not
-            // in the area covered by the image being enhanced).
-            // After an enhancement, they will be whatever is first in the "algorithm".
-            // We get round this by passing an extra argument to read_pixel, which specifies the default,
-            // and threading it through from here.
-            // This "default" will depend on which enhancement step we are on. For the first, it will always
-            // be Off. For the subsequent ones, it will be the value of either the 0th or 511th value of the
-            // "algorithm", depending on whether the previous one was Off or On. This explains the mutable
-            // "default" value here and what we do with it.
-            self.enhance(default);
-            default = match default {
-                Pixel::On => self.algorithm.0[511],
-                Pixel::Off => self.algorithm.0[0],
-            };
+            self.image = self.image.enhance(&self.algorithm);
+        }
+    }
+
+    // writes the current image, and the image after each of the given number of further
+    // enhancements, as numbered files in the given directory - ready to be made into an
+    // animation. Every frame is padded with background to the size of the last one, so
+    // that they line up, and `margin` more pixels of background are added around them all
+    // (so that any flickering of the background is visible). Returns the paths written
+    pub fn write_frames(
+        &self,
+        steps: usize,
+        margin: usize,
+        dir: &Path,
+        format: FrameFormat,
+    ) -> io::Result<Vec<PathBuf>> {
+        let mut paths = vec![];
+        let mut image = self.image.clone();
+        for step in 0..=steps {
+            if step > 0 {
+                image = image.enhance(&self.algorithm);
+            }
+            let frame_margin = steps - step + margin;
+            let path = dir.join(format!("frame_{:04}.{}", step, format.extension()));
+            let mut file = File::create(&path)?;
+            match format {
+                FrameFormat::Text => file.write_all(image.render_text(frame_margin).as_bytes())?,
+                FrameFormat::Pbm => image.write_pbm(&mut file, frame_margin)?,
+                FrameFormat::Png => image.write_png(&mut file, frame_margin)?,
+            }
+            paths.push(path);
         }
+        Ok(paths)
     }
 }
 
-fn parse_char(c: char) -> Pixel {
+fn parse_pixel(c: char) -> Option<bool> {
     match c {
-        '#' => Pixel::On,
-        '.' => Pixel::Off,
-        _ => panic!("unexpected char, {}", c),
+        '#' => Some(true),
+        '.' => Some(false),
+        _ => None,
     }
 }
 
-fn parse_algorithm(line: &str) -> EnhancementAlgorithm {
-    let pixels = line.chars().map(parse_char).collect();
-    EnhancementAlgorithm(pixels)
+fn parse_algorithm(input: Input) -> ParseResult<EnhancementAlgorithm> {
+    let line = take_while("a pixel", |c| parse_pixel(c).is_some());
+    try_map(line, |line| {
+        let pixels: Vec<bool> = line.chars().filter_map(parse_pixel).collect();
+        EnhancementAlgorithm::new(&pixels)
+    })(input)
 }
 
-fn parse_image(gridlines: &Vec<&str>) -> Image {
-    let mut pixels = HashMap::new();
-    for (row, line) in gridlines.iter().enumerate() {
-        for (col, char) in line.chars().enumerate() {
-            pixels.insert(CoOrd::new(col as isize, row as isize), parse_char(char));
-        }
-    }
-    Image(pixels)
+fn parse_puzzle(input: Input) -> ParseResult<PuzzleInfo> {
+    let (algorithm, input) = section(parse_algorithm)(input)?;
+    let image = try_map(grid("a pixel", parse_pixel), |rows| Image::new(&rows));
+    let (image, input) = section(image)(input)?;
+    Ok((PuzzleInfo::new(algorithm, image), input))
 }
 
 fn read_file() -> PuzzleInfo {
     let mut file = File::open("./input/input20.txt").unwrap();
     let mut contents = String::new();
     file.read_to_string(&mut contents).unwrap();
-    let lines: Vec<&str> = contents.lines().collect();
-    let algorithm = parse_algorithm(lines[0]);
-    let image = parse_image(&lines[2..].to_vec());
-    PuzzleInfo { algorithm, image }
+    parse_all(&contents, parse_puzzle).unwrap()
 }
 
 fn solve_part_1(mut info: PuzzleInfo) -> usize {
     info.enhance_times(2);
-    info.image
-        .0
-        .values()
-        .filter(|val| if let Pixel::On = val { true } else { false })
-        .count()
+    info.image.count_lit().unwrap()
 }
 
 fn solve_part_2(mut info: PuzzleInfo) -> usize {
     info.enhance_times(50);
-    info.image
-        .0
-        .values()
-        .filter(|val| if let Pixel::On = val { true } else { false })
-        .count()
+    info.image.count_lit().unwrap()
 }
 
 pub fn part_1() -> usize {
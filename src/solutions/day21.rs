@@ -1,3 +1,4 @@
+use super::parse::{lines, literal, parse_all, try_map, unsigned, Input, ParseResult};
 use std::cmp::max;
 use std::collections::HashMap;
 use std::convert::TryInto;
@@ -148,28 +149,34 @@ impl PlayerState {
     }
 }
 
-fn read_file() -> Game {
+fn parse_start(input: Input) -> ParseResult<u8> {
+    let (_, input) = literal("Player ")(input)?;
+    let (_, input) = unsigned::<usize>(input)?;
+    let (_, input) = literal(" starting position: ")(input)?;
+    unsigned(input)
+}
+
+// the starting space of each player
+fn read_starts() -> [u8; 2] {
     let mut file = File::open("./input/input21.txt").unwrap();
     let mut contents = String::new();
     file.read_to_string(&mut contents).unwrap();
-    let scores: Vec<u8> = contents
-        .lines()
-        .map(|line| line.chars().last().unwrap().to_string().parse().unwrap())
-        .collect();
-    Game::new(scores[0], scores[1])
+    let starts = try_map(lines(parse_start), |starts| {
+        let count = starts.len();
+        starts
+            .try_into()
+            .map_err(|_| format!("expected 2 players, found {}", count))
+    });
+    parse_all(&contents, starts).unwrap()
+}
+
+fn read_file() -> Game {
+    let [p1_space, p2_space] = read_starts();
+    Game::new(p1_space, p2_space)
 }
 
 fn read_file_2() -> [PlayerState; 2] {
-    let mut file = File::open("./input/input21.txt").unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
-    contents
-        .lines()
-        .map(|line| line.chars().last().unwrap().to_string().parse().unwrap())
-        .map(PlayerState::new)
-        .collect::<Vec<PlayerState>>()
-        .try_into()
-        .unwrap()
+    read_starts().map(PlayerState::new)
 }
 
 fn solve_part_1(mut game: Game) -> usize {
@@ -1,369 +1,504 @@
-use std::collections::HashMap;
+use super::search::{self, Graph};
+use std::collections::{HashMap, VecDeque};
 use std::fmt;
 use std::fs::File;
 use std::io::prelude::*;
 
-#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
-enum Amphipod {
-    Amber,
-    Bronze,
-    Copper,
-    Desert,
-}
-
+// amphipods are named by letter, starting from A. Each one's destination is the room with the
+// same position from the left (A in the leftmost room, B in the next, and so on), and it takes
+// 10 times as much energy to move as the one before
 #[derive(PartialEq, Eq, Hash, Clone, Copy)]
-enum BurrowSpace {
-    Corridor(u8),       // will take numbers 1-11
-    Home(Amphipod, u8), // 1-2
+pub struct Amphipod(u8);
+
+impl Amphipod {
+    fn from_letter(c: char) -> Option<Amphipod> {
+        if c.is_ascii_uppercase() {
+            Some(Amphipod(c as u8 - b'A'))
+        } else {
+            None
+        }
+    }
+
+    pub fn letter(&self) -> char {
+        (b'A' + self.0) as char
+    }
+
+    pub fn energy_per_step(&self) -> usize {
+        10_usize.pow(self.0 as u32)
+    }
+
+    fn home(&self) -> usize {
+        self.0 as usize
+    }
 }
 
-impl fmt::Debug for BurrowSpace {
+impl fmt::Debug for Amphipod {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                BurrowSpace::Corridor(n) => format!("corridor {}", n),
-                BurrowSpace::Home(amphipod, n) => format!("home {:?} {}", amphipod, n),
-            }
-        )
+        write!(f, "{}", self.letter())
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum Space {
+    // entrances are the hallway spaces just outside a room, which nobody may stop on
+    Hallway { entrance: bool },
+    // the room's position from the left, and how far in the space is (0 being nearest the hallway)
+    Room { room: usize, depth: usize },
+}
+
+// the shape of a burrow, as a graph whose nodes are the open spaces and whose edges join spaces
+// next to each other. Spaces are numbered in reading order through the diagram.
+// As amphipods never go back on themselves, the only paths that matter are the shortest ones,
+// which are all worked out up front
+pub struct Layout {
+    spaces: Vec<Space>,
+    // where each space is in the diagram, as (row, column)
+    positions: Vec<(usize, usize)>,
+    // the spaces of each room, from the hallway inwards
+    rooms: Vec<Vec<usize>>,
+    // the spaces passed through on the way from one space to another, including where it ends
+    // but not where it starts
+    paths: Vec<Vec<Vec<usize>>>,
+    // the diagram with all amphipods taken out, for drawing
+    walls: Vec<Vec<char>>,
+}
+
+impl Layout {
+    pub fn num_spaces(&self) -> usize {
+        self.spaces.len()
+    }
+
+    pub fn num_rooms(&self) -> usize {
+        self.rooms.len()
+    }
+
+    pub fn position(&self, space: usize) -> (usize, usize) {
+        self.positions[space]
+    }
+
+    fn room_of(&self, space: usize) -> Option<usize> {
+        match self.spaces[space] {
+            Space::Room { room, .. } => Some(room),
+            Space::Hallway { .. } => None,
+        }
+    }
+
+    // shortest paths from every space to every other, found by a breadth-first search from
+    // each one
+    fn find_paths(neighbours: &[Vec<usize>]) -> Vec<Vec<Vec<usize>>> {
+        (0..neighbours.len())
+            .map(|start| {
+                let mut previous = vec![None; neighbours.len()];
+                let mut queue = VecDeque::from([start]);
+                previous[start] = Some(start);
+                while let Some(space) = queue.pop_front() {
+                    for &next in &neighbours[space] {
+                        if previous[next].is_none() {
+                            previous[next] = Some(space);
+                            queue.push_back(next);
+                        }
+                    }
+                }
+                (0..neighbours.len())
+                    .map(|end| {
+                        let mut path = vec![];
+                        let mut space = end;
+                        while space != start {
+                            path.push(space);
+                            space = previous[space].unwrap();
+                        }
+                        path.reverse();
+                        path
+                    })
+                    .collect()
+            })
+            .collect()
     }
 }
 
+// where each amphipod is, indexed by the number of the space in the layout
+#[derive(PartialEq, Eq, Hash, Clone)]
+pub struct Burrow(Vec<Option<Amphipod>>);
+
 #[derive(Clone, PartialEq)]
-struct Move {
-    start: BurrowSpace,
-    end: BurrowSpace,
+pub struct Move {
+    pub amphipod: Amphipod,
+    pub start: usize,
+    pub end: usize,
+    pub energy: usize,
 }
 
 impl fmt::Debug for Move {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?} to {:?}", self.start, self.end)
+        write!(
+            f,
+            "{:?} from space {} to space {}, using {} energy",
+            self.amphipod, self.start, self.end, self.energy
+        )
     }
 }
 
-struct Burrow(HashMap<BurrowSpace, Amphipod>);
+fn is_open(c: char) -> bool {
+    c == '.' || c.is_ascii_uppercase()
+}
 
-impl Burrow {
-    fn is_finished(&self, size: u8) -> bool {
-        for amphipod_type in [
-            Amphipod::Amber,
-            Amphipod::Bronze,
-            Amphipod::Copper,
-            Amphipod::Desert,
-        ] {
-            for pos in 1..=size {
-                let home_pos = BurrowSpace::Home(amphipod_type, pos);
-                if self.0.get(&home_pos) != Some(&amphipod_type) {
-                    return false;
+// reads a diagram of any shape of burrow. The hallway is the row of open spaces (. or an
+// amphipod) nearest the top, and must be all in one piece. Each room is a column of open spaces
+// going straight down from the hallway - they can be of any depth, and there can be any number
+// of them in any places, so long as every open space is in the hallway or a room.
+// Fails if that isn't the case, or there's an amphipod with no room for it, or the number of
+// amphipods of any kind isn't the same as the number of spaces in their room
+pub fn parse_diagram(diagram: &str) -> Result<(Layout, Burrow), String> {
+    let grid: Vec<Vec<char>> = diagram.lines().map(|line| line.chars().collect()).collect();
+    let open = |row: usize, col: usize| {
+        grid.get(row)
+            .and_then(|line| line.get(col))
+            .is_some_and(|&c| is_open(c))
+    };
+
+    let hallway_row = (0..grid.len())
+        .find(|&row| (0..grid[row].len()).any(|col| open(row, col)))
+        .ok_or("the diagram has no open spaces")?;
+    let hallway: Vec<usize> = (0..grid[hallway_row].len())
+        .filter(|&col| open(hallway_row, col))
+        .collect();
+    if hallway.windows(2).any(|pair| pair[1] != pair[0] + 1) {
+        return Err("the hallway is split into pieces".to_owned());
+    }
+
+    // the column of each room, from left to right
+    let mut room_columns = vec![];
+    let mut kinds: HashMap<(usize, usize), Space> = HashMap::new();
+    for &col in &hallway {
+        let entrance = open(hallway_row + 1, col);
+        kinds.insert((hallway_row, col), Space::Hallway { entrance });
+        if entrance {
+            let room = room_columns.len();
+            room_columns.push(col);
+            let mut row = hallway_row + 1;
+            while open(row, col) {
+                let depth = row - hallway_row - 1;
+                kinds.insert((row, col), Space::Room { room, depth });
+                row += 1;
+            }
+        }
+    }
+
+    let mut spaces = vec![];
+    let mut positions = vec![];
+    let mut occupants = vec![];
+    let mut walls = grid.clone();
+    for (row, line) in grid.iter().enumerate() {
+        for (col, &c) in line.iter().enumerate() {
+            if !is_open(c) {
+                continue;
+            }
+            let space = *kinds.get(&(row, col)).ok_or_else(|| {
+                format!(
+                    "the open space at row {}, column {} is not in the hallway or a room",
+                    row + 1,
+                    col + 1
+                )
+            })?;
+            let amphipod = Amphipod::from_letter(c);
+            if let Some(amphipod) = amphipod {
+                if amphipod.home() >= room_columns.len() {
+                    return Err(format!(
+                        "there is no room for amphipod {} at row {}, column {}",
+                        c,
+                        row + 1,
+                        col + 1
+                    ));
                 }
             }
+            spaces.push(space);
+            positions.push((row, col));
+            occupants.push(amphipod);
+            walls[row][col] = '.';
         }
-        true
+    }
+
+    let index: HashMap<(usize, usize), usize> = positions
+        .iter()
+        .enumerate()
+        .map(|(space, &position)| (position, space))
+        .collect();
+    let neighbours: Vec<Vec<usize>> = positions
+        .iter()
+        .map(|&(row, col)| {
+            [
+                (row.wrapping_sub(1), col),
+                (row + 1, col),
+                (row, col.wrapping_sub(1)),
+                (row, col + 1),
+            ]
+            .iter()
+            .filter_map(|position| index.get(position).copied())
+            .collect()
+        })
+        .collect();
+
+    let mut rooms = vec![vec![]; room_columns.len()];
+    for (space, kind) in spaces.iter().enumerate() {
+        if let Space::Room { room, .. } = kind {
+            // spaces are in reading order, so each room's come from the top down
+            rooms[*room].push(space);
+        }
+    }
+
+    // the amphipods can only all get home if each kind exactly fills its room
+    let mut counts = vec![0; rooms.len()];
+    for amphipod in occupants.iter().flatten() {
+        counts[amphipod.home()] += 1;
+    }
+    for (room, spaces) in rooms.iter().enumerate() {
+        if counts[room] != spaces.len() {
+            return Err(format!(
+                "found {} of amphipod {}, but its room has {} spaces",
+                counts[room],
+                Amphipod(room as u8).letter(),
+                spaces.len()
+            ));
+        }
+    }
+
+    let layout = Layout {
+        spaces,
+        positions,
+        rooms,
+        paths: Layout::find_paths(&neighbours),
+        walls,
+    };
+    Ok((layout, Burrow(occupants)))
+}
+
+impl Graph for Layout {
+    type Node = Burrow;
+
+    fn successors(&self, burrow: &Burrow) -> Vec<(Burrow, usize)> {
+        burrow
+            .get_valid_moves(self)
+            .iter()
+            .map(|move_| (burrow.do_move(move_), move_.energy))
+            .collect()
+    }
+}
+
+impl Burrow {
+    fn is_finished(&self, layout: &Layout) -> bool {
+        layout.rooms.iter().enumerate().all(|(room, spaces)| {
+            spaces
+                .iter()
+                .all(|&space| self.0[space].is_some_and(|amphipod| amphipod.home() == room))
+        })
     }
 
     // determines which amphipod, if any, occupies a space
-    // (simply a wraper for the hashmap's get method)
-    fn get_occupier(&self, space: &BurrowSpace) -> Option<&Amphipod> {
-        self.0.get(space)
+    fn get_occupier(&self, space: usize) -> Option<Amphipod> {
+        self.0[space]
+    }
+
+    // whether an amphipod can go into its room, which is only if there are no others there
+    // which belong somewhere else
+    fn can_enter(&self, layout: &Layout, room: usize) -> bool {
+        layout.rooms[room].iter().all(|&space| {
+            self.get_occupier(space)
+                .is_none_or(|amphipod| amphipod.home() == room)
+        })
+    }
+
+    // whether an amphipod is in its own room with only its own kind further in, so that it never
+    // needs to move again. One with a free space further in still has to move, to let the rest of
+    // its kind get past
+    fn is_settled(&self, layout: &Layout, space: usize, amphipod: Amphipod) -> bool {
+        let room = &layout.rooms[amphipod.home()];
+        match room.iter().position(|&in_room| in_room == space) {
+            Some(depth) => room[depth + 1..]
+                .iter()
+                .all(|&further| self.get_occupier(further) == Some(amphipod)),
+            None => false,
+        }
+    }
+
+    // the space furthest in that's free in a room, which is where anyone going into it should go
+    fn furthest_free(&self, layout: &Layout, room: usize) -> Option<usize> {
+        layout.rooms[room]
+            .iter()
+            .rev()
+            .copied()
+            .find(|&space| self.get_occupier(space).is_none())
+    }
+
+    fn path_is_clear(&self, layout: &Layout, start: usize, end: usize) -> bool {
+        layout.paths[start][end]
+            .iter()
+            .all(|&space| self.get_occupier(space).is_none())
     }
 
     /*
     From the description, there are only 2 "types" of possible move:
-    - an amphipod moving out of a room into a corridor position.
+    - an amphipod moving out of a room, either into the hallway or straight into its own room.
     CONSTRAINTS:
-    a) can only do this if it hasn't moved yet, and if it's not starting in its destination room
+    a) can only do this if it's not already in its own room with only its own kind
     b) must be able to reach its destination (can't be blocked by other amphipods)
-    [in particular, the "second" in a room can't move out until the first one has]
-    c) must not be moving to a room "threshold" (corridor positions 3,5,7,9)
-    - moving from the corridor into a room
+    c) must not stop on an entrance to a room
+    - moving from the hallway into a room
     a) path must be clear (not blocked)
     b) room must be its home room
-    c) home room is not currently occupied by any amphipods other than its "mate"
+    c) home room is not currently occupied by any amphipods of other kinds
+    In both cases there's no point going into a room other than as far as possible.
     These rules are encoded in the following method.
     */
-    //major rewrite needed for part 2:
-    //- anything that refers to front_pos and back_pos (just first branch) needs to be more generic.
-    //- similarly, the part where we subtract from 3 to get the "other pos", either pass new "size" param
-    //and subtract from (1 + size), or do differently!
-    fn get_valid_moves(&self, size: u8) -> Vec<(Move, usize)> {
+    fn get_valid_moves(&self, layout: &Layout) -> Vec<Move> {
         let mut valid = vec![];
-        'outer: for (position, &amphipod_type) in &self.0 {
-            match position {
-                BurrowSpace::Corridor(pos) => {
-                    // can only move into destination room - determine which space
-                    // and check path is free
-                    let mut destination = BurrowSpace::Home(amphipod_type, 1);
-                    for home_pos in (2..=size).rev() {
-                        let test_pos = BurrowSpace::Home(amphipod_type, home_pos);
-                        match self.get_occupier(&test_pos) {
-                            None => {
-                                destination = test_pos;
-                                break;
-                            }
-                            Some(already_home) => {
-                                // can't move home if another amphipod of the wrong time is still
-                                // there
-                                if already_home != &amphipod_type {
-                                    continue 'outer;
-                                }
-                            }
-                        }
-                    }
-                    let threshold_position = match amphipod_type {
-                        Amphipod::Amber => 3,
-                        Amphipod::Bronze => 5,
-                        Amphipod::Copper => 7,
-                        Amphipod::Desert => 9,
-                    };
-                    let positions = if *pos < threshold_position {
-                        (*pos + 1)..=threshold_position
-                    } else {
-                        threshold_position..=(*pos - 1)
-                    };
-                    let mut spaces: Vec<BurrowSpace> =
-                        positions.map(BurrowSpace::Corridor).collect();
-                    if let BurrowSpace::Home(_, pos) = destination {
-                        for intervening in 1..pos {
-                            spaces.push(BurrowSpace::Home(amphipod_type, intervening));
-                        }
-                    }
-                    spaces.push(destination);
-                    if spaces
-                        .iter()
-                        .all(|space| self.get_occupier(space).is_none())
-                    {
-                        let valid_move = Move {
-                            start: *position,
-                            end: destination,
-                        };
-                        let path_length = spaces.len();
-                        let cost = match amphipod_type {
-                            Amphipod::Amber => 1,
-                            Amphipod::Bronze => 10,
-                            Amphipod::Copper => 100,
-                            Amphipod::Desert => 1000,
-                        };
-                        let total_cost = cost * path_length;
-                        valid.push((valid_move, total_cost));
-                    }
-                }
-                BurrowSpace::Home(home_type, home_pos) => {
-                    // we can't move the amphipod if it's in its correct home and
-                    // all other home space have an amphipod of the same type or
-                    // are empty
-                    let mut is_valid = false;
-                    if home_type == &amphipod_type {
-                        for other_pos in 1..=size {
-                            if let Some(occupying_type) =
-                                self.get_occupier(&BurrowSpace::Home(*home_type, other_pos))
-                            {
-                                if occupying_type != home_type {
-                                    is_valid = true;
-                                    break;
-                                }
-                            }
-                        }
-                    } else {
-                        is_valid = true;
-                    }
-                    if !is_valid {
-                        continue 'outer;
-                    }
-                    // check available spaces in corridor, that aren't thresholds
-                    let threshold_position = match home_type {
-                        Amphipod::Amber => 3,
-                        Amphipod::Bronze => 5,
-                        Amphipod::Copper => 7,
-                        Amphipod::Desert => 9,
-                    };
-                    let illegal_destinations = vec![3, 5, 7, 9];
-                    let positions = 1..=11;
-                    let spaces: Vec<BurrowSpace> = positions
-                        .filter(|pos| !illegal_destinations.contains(pos))
-                        .map(BurrowSpace::Corridor)
-                        .collect();
-
-                    for space in spaces {
-                        // can't fail, and no need for sanity check
-                        if let BurrowSpace::Corridor(destination) = space {
-                            let positions = if destination < threshold_position {
-                                destination..=threshold_position
-                            } else {
-                                threshold_position..=destination
-                            };
-                            let mut path: Vec<BurrowSpace> =
-                                positions.map(|pos| BurrowSpace::Corridor(pos)).collect();
-
-                            for pos in 1..*home_pos {
-                                path.push(BurrowSpace::Home(*home_type, pos));
-                            }
-
-                            if path.iter().all(|space| self.get_occupier(space).is_none()) {
-                                let valid_move = Move {
-                                    start: *position,
-                                    end: BurrowSpace::Corridor(destination),
-                                };
-                                let path_length = path.len();
-                                let cost = match amphipod_type {
-                                    Amphipod::Amber => 1,
-                                    Amphipod::Bronze => 10,
-                                    Amphipod::Copper => 100,
-                                    Amphipod::Desert => 1000,
-                                };
-                                let total_cost = cost * path_length;
-                                valid.push((valid_move, total_cost));
-                            }
-                        }
-                    }
+        for (start, occupier) in self.0.iter().enumerate() {
+            let amphipod = match occupier {
+                Some(amphipod) => *amphipod,
+                None => continue,
+            };
+            if self.is_settled(layout, start, amphipod) {
+                continue;
+            }
+            let home = amphipod.home();
+            let mut ends = vec![];
+            // one that's in its room but not settled has to leave and come back
+            if self.can_enter(layout, home) && layout.room_of(start) != Some(home) {
+                ends.extend(self.furthest_free(layout, home));
+            }
+            if layout.room_of(start).is_some() {
+                ends.extend(
+                    (0..layout.num_spaces())
+                        .filter(|&end| layout.spaces[end] == Space::Hallway { entrance: false }),
+                );
+            }
+            for end in ends {
+                if self.path_is_clear(layout, start, end) {
+                    let steps = layout.paths[start][end].len();
+                    valid.push(Move {
+                        amphipod,
+                        start,
+                        end,
+                        energy: steps * amphipod.energy_per_step(),
+                    });
                 }
             }
         }
         valid
     }
 
-    // doesn't mutate so we can do a DFS of all moves without messing things up
     fn do_move(&self, move_: &Move) -> Self {
         let mut new_positions = self.0.clone();
-        let amphipod = self.0.get(&move_.start).unwrap();
-        new_positions.remove(&move_.start);
-        new_positions.insert(move_.end, *amphipod);
+        new_positions[move_.start] = None;
+        new_positions[move_.end] = Some(move_.amphipod);
         Self(new_positions)
     }
 
-    // now about 40-45 seconds in release mode, for test data.
-    // around 2 hours for real data (between 1 and 2 anyway - I left it running...)
-    // Not good, but at least it's an answer!
-    // For part 2, takes even longer. After about 2-and-a-half hours of running I returned to
-    // it and put in the latest "shortest found", and before long got it right!
-    fn find_solutions_and_cost_recursive(
-        &self,
-        size: u8,
-        current_path: Vec<Move>,
-        current_cost: usize,
-        lowest_cost: Option<usize>,
-    ) -> Option<usize> {
-        // if current cost is already too high, abandon the search
-        if let Some(cost) = lowest_cost {
-            if current_cost >= cost {
-                return None;
-            }
-        }
+    // the cheapest sequence of moves that gets every amphipod home, if it can be done at all.
+    // This is the cheapest path through the graph of every position the amphipods can get into,
+    // so Dijkstra's algorithm finds it
+    pub fn find_best_moves(&self, layout: &Layout) -> Option<Vec<Move>> {
+        let path = search::dijkstra(layout, self.clone(), |burrow| burrow.is_finished(layout))?;
+        // the path only gives the positions, so work out which move gets from each to the next
+        let moves = path
+            .nodes
+            .windows(2)
+            .map(|pair| {
+                pair[0]
+                    .get_valid_moves(layout)
+                    .into_iter()
+                    .filter(|move_| pair[0].do_move(move_) == pair[1])
+                    .min_by_key(|move_| move_.energy)
+                    .unwrap()
+            })
+            .collect();
+        Some(moves)
+    }
 
-        // first "update" the burrow to the situation after following the "path"
-        let mut updated = self;
-        let mut next;
-        for move_ in &current_path {
-            next = updated.do_move(move_);
-            updated = &next;
-        }
+    // the least energy needed to get every amphipod home, if it can be done at all
+    pub fn find_lowest_cost(&self, layout: &Layout) -> Option<usize> {
+        self.find_best_moves(layout)
+            .map(|moves| moves.iter().map(|move_| move_.energy).sum())
+    }
 
-        // if we've finished the current path, check its cost and mark it as new lowest if apprporiate,
-        // then (whether lowest or not) exit by returning
-        if updated.is_finished(size) {
-            return match lowest_cost {
-                None => Some(current_cost),
-                Some(old) => {
-                    if current_cost < old {
-                        // println!("found shortest path so far of cost {}", current_cost);
-                        Some(current_cost)
-                    } else {
-                        None
-                    }
-                }
-            };
+    // the burrow after each of the moves in turn, starting with how it is now
+    pub fn replay(&self, moves: &[Move]) -> Vec<Burrow> {
+        let mut burrows = vec![self.clone()];
+        for move_ in moves {
+            let next = burrows.last().unwrap().do_move(move_);
+            burrows.push(next);
         }
+        burrows
+    }
 
-        // find possible moves from where we are.
-        let next_moves = updated.get_valid_moves(size);
-
-        // If none, exit this path without checking the cost
-        if next_moves.is_empty() {
-            return None;
+    // draws the burrow in the same way as the diagrams in the puzzle
+    pub fn render(&self, layout: &Layout) -> String {
+        let mut grid = layout.walls.clone();
+        for (space, occupier) in self.0.iter().enumerate() {
+            let (row, col) = layout.position(space);
+            grid[row][col] = occupier.map_or('.', |amphipod| amphipod.letter());
         }
-
-        // For each move, add to path and recursively call.
-        let mut new_lowest = lowest_cost;
-        for (move_, cost) in next_moves {
-            let mut new_path = current_path.clone();
-            new_path.push(move_);
-            let new_cost = current_cost + cost;
-            if let Some(lower) =
-                self.find_solutions_and_cost_recursive(size, new_path, new_cost, new_lowest)
-            {
-                new_lowest = Some(lower);
-            }
+        let mut text = String::new();
+        for row in grid {
+            text.extend(row);
+            text.push('\n');
         }
-        // for info while running!
-        /* if current_path.len() <= 3 {
-            println!("completely searched path beginning {:?}", current_path);
-        } */
-        new_lowest
-    }
-
-    fn find_lowest_cost(&self, size: u8) -> usize {
-        self.find_solutions_and_cost_recursive(size, vec![], 0, None)
-            .unwrap()
+        text
     }
 }
 
-fn read_position(col: u8, pos: u8) -> BurrowSpace {
-    match col {
-        3 => BurrowSpace::Home(Amphipod::Amber, pos),
-        5 => BurrowSpace::Home(Amphipod::Bronze, pos),
-        7 => BurrowSpace::Home(Amphipod::Copper, pos),
-        9 => BurrowSpace::Home(Amphipod::Desert, pos),
-        _ => panic!("didn't expect to find amphipod at column {}", col),
+// the starting burrow and the burrow after each move, with what the move was and how much energy
+// it took, ending with the total energy used
+pub fn describe_moves(layout: &Layout, burrow: &Burrow, moves: &[Move]) -> String {
+    let burrows = burrow.replay(moves);
+    let mut text = burrows[0].render(layout);
+    for (move_, after) in moves.iter().zip(&burrows[1..]) {
+        let (start_row, start_col) = layout.position(move_.start);
+        let (end_row, end_col) = layout.position(move_.end);
+        text.push_str(&format!(
+            "\n{:?} moves from row {}, column {} to row {}, column {}, using {} energy:\n",
+            move_.amphipod,
+            start_row + 1,
+            start_col + 1,
+            end_row + 1,
+            end_col + 1,
+            move_.energy
+        ));
+        text.push_str(&after.render(layout));
     }
+    let total: usize = moves.iter().map(|move_| move_.energy).sum();
+    text.push_str(&format!("\nTotal energy used: {}\n", total));
+    text
 }
 
-fn parse_amphipod(c: char) -> Option<Amphipod> {
-    match c {
-        'A' => Some(Amphipod::Amber),
-        'B' => Some(Amphipod::Bronze),
-        'C' => Some(Amphipod::Copper),
-        'D' => Some(Amphipod::Desert),
-        '#' => None,
-        ' ' => None,
-        _ => panic!("unexpected char in file: {}", c),
-    }
+// the two lines the full diagram has between the first and second rows of the rooms
+const FOLDED_LINES: [&str; 2] = ["  #D#C#B#A#", "  #D#B#A#C#"];
+
+fn unfold(diagram: &str) -> String {
+    let mut lines: Vec<&str> = diagram.lines().collect();
+    lines.splice(3..3, FOLDED_LINES);
+    lines.join("\n")
 }
 
-fn read_file(extend: bool) -> Burrow {
+fn read_file() -> String {
     let mut file = File::open("./input/input23.txt").unwrap();
     let mut contents = String::new();
     file.read_to_string(&mut contents).unwrap();
-    let mut lines: Vec<&str> = contents.lines().collect();
-    if extend {
-        lines.insert(3, "  #D#C#B#A#");
-        lines.insert(4, "  #D#B#A#C#");
-    }
-    let mut positions = HashMap::new();
-
-    let mut home_pos = 1;
-    for line in lines.iter().skip(2) {
-        for (col, c) in line.chars().enumerate() {
-            if let Some(amphipod) = parse_amphipod(c) {
-                let position = read_position(col as u8, home_pos);
-                positions.insert(position, amphipod);
-            }
-        }
-        home_pos += 1;
-    }
-
-    Burrow(positions)
+    contents
 }
 
-fn solve(burrow: Burrow, size: u8) -> usize {
-    burrow.find_lowest_cost(size)
+fn solve(diagram: &str) -> usize {
+    let (layout, burrow) = parse_diagram(diagram).unwrap();
+    burrow
+        .find_lowest_cost(&layout)
+        .expect("the amphipods can't be organised!")
 }
 
 pub fn part_1() -> usize {
-    let burrow = read_file(false);
-    solve(burrow, 2)
+    let diagram = read_file();
+    solve(&diagram)
 }
 
 pub fn part_2() -> usize {
-    let extended_burrow = read_file(true);
-    solve(extended_burrow, 4)
+    let diagram = unfold(&read_file());
+    solve(&diagram)
 }
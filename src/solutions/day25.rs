@@ -1,119 +1,230 @@
 use std::fs::File;
 use std::io::prelude::*;
 
-enum CucumberStatus {
-    Right,
-    Down,
-    None,
+// each herd is stored as one bitmask per row, with bit i of a row set if there is a cucumber
+// of that herd in column i. Rows longer than 64 columns take up several words.
+// This lets a whole row of cucumbers be moved at once with a few shifts: an east-facing one
+// moves if the bit to its right (wrapping round) is clear in both herds, and a south-facing
+// one moves if the same bit in the row below (again wrapping) is clear in both.
+pub struct Cucumbers {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    east: Vec<u64>,
+    south: Vec<u64>,
 }
 
-struct Cucumbers(Vec<Vec<CucumberStatus>>);
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    // the number of the first step on which nothing moved
+    Stabilised { steps: usize },
+    // the herds never stop moving, but are found to repeat the same positions every `period`
+    // steps, which was noticed after `steps` steps
+    Cycles { steps: usize, period: usize },
+}
 
 impl Cucumbers {
-    fn get_width(&self) -> usize {
-        self.0[0].len()
+    // fails if the lines aren't all the same length or contain an unexpected character
+    pub fn parse(lines: &[&str]) -> Result<Cucumbers, String> {
+        let width = lines.first().map_or(0, |line| line.len());
+        let height = lines.len();
+        let words_per_row = width.div_ceil(64);
+        let mut east = vec![0; words_per_row * height];
+        let mut south = vec![0; words_per_row * height];
+        for (row, line) in lines.iter().enumerate() {
+            if line.len() != width {
+                return Err(format!("row {} is a different length to the first", row));
+            }
+            for (col, c) in line.chars().enumerate() {
+                let word = row * words_per_row + col / 64;
+                let bit = 1 << (col % 64);
+                match c {
+                    '>' => east[word] |= bit,
+                    'v' => south[word] |= bit,
+                    '.' => (),
+                    _ => return Err(format!("unexpected character {}", c)),
+                }
+            }
+        }
+        Ok(Cucumbers {
+            width,
+            height,
+            words_per_row,
+            east,
+            south,
+        })
     }
 
-    fn get_height(&self) -> usize {
-        self.0.len()
+    fn row<'a>(&self, herd: &'a [u64], row: usize) -> &'a [u64] {
+        &herd[row * self.words_per_row..(row + 1) * self.words_per_row]
     }
 
-    fn read(&self, row: usize, col: usize) -> &CucumberStatus {
-        &self.0[row][col]
+    // bit i of the result is bit i + 1 of the row, wrapping round at the end
+    fn shift_from_next_column(&self, row: &[u64]) -> Vec<u64> {
+        let mut result = vec![0; row.len()];
+        for word in 0..row.len() {
+            result[word] = row[word] >> 1;
+            if word + 1 < row.len() {
+                result[word] |= row[word + 1] << 63;
+            }
+        }
+        let last = self.width - 1;
+        if row[0] & 1 != 0 {
+            result[last / 64] |= 1 << (last % 64);
+        }
+        result
     }
 
-    fn get_move_position(&self, row: usize, col: usize) -> Option<(usize, usize)> {
-        let status = self.read(row, col);
-        match status {
-            &CucumberStatus::Down => {
-                let mut new_row = row + 1;
-                if new_row == self.get_height() {
-                    new_row = 0;
-                }
-                if let &CucumberStatus::None = self.read(new_row, col) {
-                    Some((new_row, col))
-                } else {
-                    None
-                }
+    // bit i + 1 of the result is bit i of the row, wrapping round at the end
+    fn shift_to_next_column(&self, row: &[u64]) -> Vec<u64> {
+        let mut result = vec![0; row.len()];
+        for word in 0..row.len() {
+            result[word] = row[word] << 1;
+            if word > 0 {
+                result[word] |= row[word - 1] >> 63;
             }
-            &CucumberStatus::Right => {
-                let mut new_col = col + 1;
-                if new_col == self.get_width() {
-                    new_col = 0;
-                }
-                if let &CucumberStatus::None = self.read(row, new_col) {
-                    Some((row, new_col))
-                } else {
-                    None
-                }
-            }
-            &CucumberStatus::None => None,
         }
+        let last = self.width - 1;
+        if !self.width.is_multiple_of(64) {
+            result[self.width / 64] &= !(1 << (self.width % 64));
+        }
+        if row[last / 64] & (1 << (last % 64)) != 0 {
+            result[0] |= 1;
+        }
+        result
+    }
+
+    // the columns in the given row that are free, as a bitmask
+    fn free(&self, row: usize) -> Vec<u64> {
+        let east = self.row(&self.east, row);
+        let south = self.row(&self.south, row);
+        let mut free: Vec<u64> = east.iter().zip(south).map(|(e, s)| !(e | s)).collect();
+        if !self.width.is_multiple_of(64) {
+            free[self.words_per_row - 1] &= (1 << (self.width % 64)) - 1;
+        }
+        free
     }
 
     // return value indicates whether any updates were made or not
     fn step_right(&mut self) -> bool {
-        let width = self.get_width();
-        let height = self.get_height();
-        let mut moved = vec![];
-        let mut destinations = vec![];
-        for row in 0..height {
-            for col in 0..width {
-                if let CucumberStatus::Right = self.read(row, col) {
-                    if let Some((new_row, new_col)) = self.get_move_position(row, col) {
-                        moved.push((row, col));
-                        destinations.push((new_row, new_col));
-                    }
-                }
+        let mut any_moved = false;
+        for row in 0..self.height {
+            let can_enter = self.shift_from_next_column(&self.free(row));
+            let current = self.row(&self.east, row);
+            let movers: Vec<u64> = current.iter().zip(&can_enter).map(|(e, c)| e & c).collect();
+            if movers.iter().all(|&word| word == 0) {
+                continue;
+            }
+            any_moved = true;
+            let arrived = self.shift_to_next_column(&movers);
+            let start = row * self.words_per_row;
+            for word in 0..self.words_per_row {
+                let east = &mut self.east[start + word];
+                *east = (*east & !movers[word]) | arrived[word];
             }
         }
-        for i in 0..(moved.len()) {
-            let (old_row, old_col) = moved[i];
-            let (new_row, new_col) = destinations[i];
-            self.0[old_row][old_col] = CucumberStatus::None;
-            self.0[new_row][new_col] = CucumberStatus::Right;
-        }
-        !moved.is_empty()
+        any_moved
     }
 
-    // return value indicates whether any updates were made or not
+    // return value indicates whether any updates were made or not.
+    // The south herd uses the same row masks as the east herd, rather than masks per column:
+    // moving down is then just ANDing each row with the free mask of the row below, with no
+    // shifting at all. Column masks would make this shift-based too, but the free spaces would
+    // then be needed in both layouts, so one herd or the other would have to be transposed on
+    // every step
     fn step_down(&mut self) -> bool {
-        let width = self.get_width();
-        let height = self.get_height();
-        let mut moved = vec![];
-        let mut destinations = vec![];
-        for row in 0..height {
-            for col in 0..width {
-                if let CucumberStatus::Down = self.read(row, col) {
-                    if let Some((new_row, new_col)) = self.get_move_position(row, col) {
-                        moved.push((row, col));
-                        destinations.push((new_row, new_col));
-                    }
+        if self.height == 0 {
+            return false;
+        }
+        // work out who moves from every row before moving anyone
+        let movers: Vec<Vec<u64>> = (0..self.height)
+            .map(|row| {
+                let below = self.free((row + 1) % self.height);
+                let current = self.row(&self.south, row);
+                current.iter().zip(&below).map(|(s, f)| s & f).collect()
+            })
+            .collect();
+        let mut any_moved = false;
+        for (row, row_movers) in movers.iter().enumerate() {
+            let below = (row + 1) % self.height;
+            for (word, &moving) in row_movers.iter().enumerate() {
+                if moving != 0 {
+                    any_moved = true;
+                    self.south[row * self.words_per_row + word] &= !moving;
+                    self.south[below * self.words_per_row + word] |= moving;
                 }
             }
         }
-        for i in 0..(moved.len()) {
-            let (old_row, old_col) = moved[i];
-            let (new_row, new_col) = destinations[i];
-            self.0[old_row][old_col] = CucumberStatus::None;
-            self.0[new_row][new_col] = CucumberStatus::Down;
-        }
-        !moved.is_empty()
+        any_moved
     }
 
-    fn step_both(&mut self) -> bool {
+    pub fn step_both(&mut self) -> bool {
+        if self.width == 0 {
+            return false;
+        }
         let moved_right = self.step_right();
         let moved_down = self.step_down();
         moved_right || moved_down
     }
-}
 
-fn parse_cucumber(c: char) -> CucumberStatus {
-    match c {
-        'v' => CucumberStatus::Down,
-        '>' => CucumberStatus::Right,
-        '.' => CucumberStatus::None,
-        _ => panic!("unexpected character {}", c),
+    fn same_positions(&self, other: &Cucumbers) -> bool {
+        self.east == other.east && self.south == other.south
+    }
+
+    fn snapshot(&self) -> Cucumbers {
+        Cucumbers {
+            east: self.east.clone(),
+            south: self.south.clone(),
+            ..*self
+        }
+    }
+
+    // keeps stepping until nothing moves, or the positions are found to repeat.
+    // Repeats are found with Brent's algorithm: we keep a copy of the positions from the last
+    // step that was a power of 2, and compare each new state with it. Once the steps taken
+    // since then are at least the length of the cycle, it is bound to be seen again
+    pub fn run(&mut self) -> Outcome {
+        let mut steps = 0;
+        let mut saved = self.snapshot();
+        let mut since_saved = 0;
+        let mut next_save = 1;
+        loop {
+            steps += 1;
+            if !self.step_both() {
+                return Outcome::Stabilised { steps };
+            }
+            since_saved += 1;
+            if self.same_positions(&saved) {
+                return Outcome::Cycles {
+                    steps,
+                    period: since_saved,
+                };
+            }
+            if since_saved == next_save {
+                saved = self.snapshot();
+                since_saved = 0;
+                next_save *= 2;
+            }
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let word = row * self.words_per_row + col / 64;
+                let bit = 1 << (col % 64);
+                text.push(if self.east[word] & bit != 0 {
+                    '>'
+                } else if self.south[word] & bit != 0 {
+                    'v'
+                } else {
+                    '.'
+                });
+            }
+            text.push('\n');
+        }
+        text
     }
 }
 
@@ -121,21 +232,20 @@ fn read_file() -> Cucumbers {
     let mut file = File::open("./input/input25.txt").unwrap();
     let mut contents = String::new();
     file.read_to_string(&mut contents).unwrap();
-    let cucumbers = contents
-        .lines()
-        .map(|line| line.chars().map(parse_cucumber).collect())
-        .collect();
-    Cucumbers(cucumbers)
+    let lines: Vec<&str> = contents.lines().collect();
+    Cucumbers::parse(&lines).unwrap()
 }
 
 fn solve_part_1(mut cucumbers: Cucumbers) -> usize {
-    let mut steps = 0;
-    let mut to_move = true;
-    while to_move {
-        to_move = cucumbers.step_both();
-        steps += 1;
+    match cucumbers.run() {
+        Outcome::Stabilised { steps } => steps,
+        Outcome::Cycles { period, .. } => {
+            panic!(
+                "cucumbers never stop moving, repeating every {} steps",
+                period
+            )
+        }
     }
-    steps
 }
 
 pub fn part_1() -> usize {
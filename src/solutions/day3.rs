@@ -1,108 +1,118 @@
 use std::fs::File;
 use std::io::prelude::*;
 
-#[derive(Clone)]
-struct BinaryNumber {
-    bits: [bool; 12],
-}
-
-impl BinaryNumber {
-    fn to_num(&self) -> usize {
-        let mut binary_string = String::from("");
-        for bit in self.bits {
-            binary_string.push_str(if bit { "1" } else { "0" });
-        }
-        usize::from_str_radix(&binary_string, 2).unwrap()
-    }
-}
-
-struct Diagnostic {
-    nums: Vec<BinaryNumber>,
+pub struct Diagnostic {
+    // the numbers are stored as integers, with the first character of each line as the
+    // most significant of the `width` bits
+    nums: Vec<usize>,
+    width: usize,
 }
 
 impl Diagnostic {
-    fn most_common(&self, idx: usize) -> bool {
-        let target = (&self.nums.len() / 2) + 1;
-        let mut one_count = 0;
-        let mut zero_count = 0;
-        for num in &self.nums {
-            let bit = num.bits[idx];
-            if bit {
-                one_count += 1;
-            } else {
-                zero_count += 1;
-            }
-            if one_count >= target {
-                return true;
+    // the width is taken from the lines themselves, and all must match it. Fails if there are
+    // no lines, they are different widths, or there is a character other than 0 or 1
+    pub fn parse(lines: &[&str]) -> Result<Diagnostic, String> {
+        let width = match lines.first() {
+            Some(line) => line.len(),
+            None => return Err("there are no numbers in the report".to_owned()),
+        };
+        if width == 0 || width >= usize::BITS as usize {
+            return Err(format!("can't handle numbers with {} bits", width));
+        }
+        let mut nums = vec![];
+        for (line_number, line) in lines.iter().enumerate() {
+            if line.len() != width {
+                return Err(format!(
+                    "line {} has {} bits, but the first line has {}",
+                    line_number + 1,
+                    line.len(),
+                    width
+                ));
             }
-            if zero_count >= target {
-                return false;
+            let mut num = 0;
+            for bit in line.chars() {
+                num <<= 1;
+                match bit {
+                    '0' => (),
+                    '1' => num |= 1,
+                    _ => {
+                        return Err(format!(
+                            "found character {} that wasn't 0 or 1 on line {}",
+                            bit,
+                            line_number + 1
+                        ))
+                    }
+                }
             }
+            nums.push(num);
         }
-        // if we get here, both bits are equally common. By convention we return 1 (true),
-        // to match with the puzzle instructions
-        true
+        Ok(Diagnostic { nums, width })
     }
 
-    fn gamma_and_epsilon(&self) -> (usize, usize) {
-        let mut gamma_str = String::from("");
-        let mut epsilon_str = String::from("");
-        for idx in 0..12 {
-            let most_common = self.most_common(idx);
-            gamma_str.push_str(if most_common { "1" } else { "0" });
-            epsilon_str.push_str(if most_common { "0" } else { "1" });
-        }
-        let gamma = usize::from_str_radix(&gamma_str, 2).unwrap();
-        let epsilon = usize::from_str_radix(&epsilon_str, 2).unwrap();
-        (gamma, epsilon)
+    // the bit for the given column (counting from the left) as a mask
+    fn mask(&self, idx: usize) -> usize {
+        1 << (self.width - 1 - idx)
+    }
+
+    // the number of 1s in each column
+    pub fn column_counts(&self) -> Vec<usize> {
+        (0..self.width)
+            .map(|idx| self.count_ones(&self.nums, idx))
+            .collect()
+    }
+
+    fn count_ones(&self, nums: &[usize], idx: usize) -> usize {
+        let mask = self.mask(idx);
+        nums.iter().filter(|&&num| num & mask != 0).count()
     }
 
-    fn filter(&self, idx: usize, most_or_least: bool) -> Self {
-        let commonest = self.most_common(idx);
-        let wanted = if most_or_least { commonest } else { !commonest };
-        let to_keep = self
-            .nums
-            .iter()
-            .filter(|bin| bin.bits[idx] == wanted)
-            .map(|num| num.clone())
-            .collect();
-        Diagnostic { nums: to_keep }
+    // if both bits are equally common, by convention we return 1 (true),
+    // to match with the puzzle instructions
+    fn most_common(&self, nums: &[usize], idx: usize) -> bool {
+        let ones = self.count_ones(nums, idx);
+        ones * 2 >= nums.len()
     }
 
-    fn full_filter(&self, most_or_least: bool) -> usize {
-        let mut i = 0;
-        let mut current = self;
-        let mut new: Self;
-        loop {
-            if current.nums.len() == 1 {
-                let remaining = &current.nums[0];
-                return remaining.to_num();
+    pub fn gamma_and_epsilon(&self) -> (usize, usize) {
+        let mut gamma = 0;
+        for (idx, ones) in self.column_counts().into_iter().enumerate() {
+            if ones * 2 >= self.nums.len() {
+                gamma |= self.mask(idx);
             }
-            new = current.filter(i, most_or_least);
-            current = &new;
-            i += 1;
         }
+        let all_bits = (1 << self.width) - 1;
+        let epsilon = !gamma & all_bits;
+        (gamma, epsilon)
     }
-}
 
-fn parse_line(s: &str) -> BinaryNumber {
-    let mut bits = [false; 12];
-    for (idx, bit) in s.chars().enumerate() {
-        match bit {
-            '0' => (),
-            '1' => bits[idx] = true,
-            _ => panic!("Found character that wasn't 0 or 1 in input!"),
+    // repeatedly filters the numbers to keep those with the most (or least) common bit in each
+    // column until only one is left. Fails if no numbers are left at any point, or more than
+    // one is left after every column has been used
+    pub fn full_filter(&self, most_or_least: bool) -> Result<usize, String> {
+        let mut current = self.nums.clone();
+        for idx in 0..self.width {
+            if current.len() == 1 {
+                break;
+            }
+            let commonest = self.most_common(&current, idx);
+            let wanted = if most_or_least { commonest } else { !commonest };
+            let mask = self.mask(idx);
+            current.retain(|&num| (num & mask != 0) == wanted);
+        }
+        match current.len() {
+            0 => Err("no numbers left after filtering".to_owned()),
+            1 => Ok(current[0]),
+            n => Err(format!("{} numbers still left after filtering", n)),
         }
     }
-    BinaryNumber { bits }
 }
 
 fn read_file() -> Diagnostic {
     let mut file = File::open("./input/input3.txt").unwrap();
     let mut contents = String::new();
     file.read_to_string(&mut contents).unwrap();
-    let nums = contents.lines().map(parse_line).collect();
-    Diagnostic { nums }
+    let lines: Vec<&str> = contents.lines().collect();
+    Diagnostic::parse(&lines).unwrap()
 }
 
 fn solve_part_1(nums: Diagnostic) -> usize {
@@ -111,8 +121,8 @@ fn solve_part_1(nums: Diagnostic) -> usize {
 }
 
 fn solve_part_2(nums: Diagnostic) -> usize {
-    let oxygen = nums.full_filter(true);
-    let co2 = nums.full_filter(false);
+    let oxygen = nums.full_filter(true).unwrap();
+    let co2 = nums.full_filter(false).unwrap();
     oxygen * co2
 }
 
@@ -1,24 +1,52 @@
-use itertools::Itertools;
-use std::convert::TryInto;
+use super::parse::{
+    lines, literal, optional, parse_all, section, sections, separated, spaces, try_map, unsigned,
+    Input, ParseResult,
+};
+use std::collections::VecDeque;
 use std::fs::File;
 use std::io::prelude::*;
 
-struct Bingo {
-    board: [[usize; 5]; 5],
-    covered: Vec<(usize, usize)>,
+// the board state. Boards can be any rectangular size
+pub struct Bingo {
+    board: Vec<Vec<usize>>,
+    covered: Vec<Vec<bool>>,
     has_won: bool,
 }
 
 impl Bingo {
+    // fails if the board is empty or not rectangular
+    pub fn new(board: Vec<Vec<usize>>) -> Result<Bingo, String> {
+        let width = board.first().map_or(0, |row| row.len());
+        if width == 0 {
+            return Err("bingo board is empty".to_owned());
+        }
+        if board.iter().any(|row| row.len() != width) {
+            return Err("bingo board rows are not all the same length".to_owned());
+        }
+        let covered = vec![vec![false; width]; board.len()];
+        Ok(Bingo {
+            board,
+            covered,
+            has_won: false,
+        })
+    }
+
+    fn height(&self) -> usize {
+        self.board.len()
+    }
+
+    fn width(&self) -> usize {
+        self.board[0].len()
+    }
+
     // takes a number that has just been called, checks it against the given grid
-    // and updates the "covered" list if appropriate. If so, it returns the row and column
+    // and updates the "covered" grid if appropriate. If so, it returns the row and column
     // covered, wrapped in Some - otherwise returns None
     fn check_number(&mut self, called: usize) -> Option<(usize, usize)> {
-        for row in 0..5 {
-            for col in 0..5 {
-                let number = self.board[row][col];
-                if number == called {
-                    self.covered.push((row, col));
+        for row in 0..self.height() {
+            for col in 0..self.width() {
+                if self.board[row][col] == called && !self.covered[row][col] {
+                    self.covered[row][col] = true;
                     return Some((row, col));
                 }
             }
@@ -26,33 +54,21 @@ impl Bingo {
         None
     }
 
-    // does as check_number, but now the return value indicates if the board has a line
-    // (ie "has won") or not
-    fn check_win(&mut self, called: usize) -> bool {
-        let covered = self.check_number(called);
-        if let Some((row, col)) = covered {
-            let mut has_won = true;
-            // first check row
-            for col_to_check in 0..5 {
-                if !self.covered.contains(&(row, col_to_check)) {
-                    has_won = false;
-                    break;
-                }
-            }
-            if has_won {
-                self.has_won = true;
+    // checks if covering the given square has completed a line through it
+    fn completes_line(&self, row: usize, col: usize, rules: &Rules) -> bool {
+        if self.covered[row].iter().all(|&covered| covered) {
+            return true;
+        }
+        if self.covered.iter().all(|covered_row| covered_row[col]) {
+            return true;
+        }
+        // diagonals only make sense on square boards
+        if rules.diagonals && self.width() == self.height() {
+            let size = self.width();
+            if row == col && (0..size).all(|i| self.covered[i][i]) {
                 return true;
             }
-            // then column
-            has_won = true;
-            for row_to_check in 0..5 {
-                if !self.covered.contains(&(row_to_check, col)) {
-                    has_won = false;
-                    break;
-                }
-            }
-            if has_won {
-                self.has_won = true;
+            if row + col == size - 1 && (0..size).all(|i| self.covered[i][size - 1 - i]) {
                 return true;
             }
         }
@@ -61,10 +77,10 @@ impl Bingo {
 
     fn sum_uncovered(&self) -> usize {
         let mut sum = 0;
-        for row in 0..5 {
-            for col in 0..5 {
-                if !self.covered.contains(&(row, col)) {
-                    sum += self.board[row][col];
+        for (numbers, covered) in self.board.iter().zip(&self.covered) {
+            for (number, &is_covered) in numbers.iter().zip(covered) {
+                if !is_covered {
+                    sum += number;
                 }
             }
         }
@@ -72,91 +88,150 @@ impl Bingo {
     }
 }
 
-struct Game {
+#[derive(Clone, Copy, Default)]
+pub struct Rules {
+    // whether a complete diagonal counts as a win (on square boards)
+    pub diagonals: bool,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Event {
+    NumberCalled {
+        number: usize,
+    },
+    BoardMarked {
+        board: usize,
+        row: usize,
+        col: usize,
+    },
+    // rank is 1 for the first board to win, 2 for the second, and so on. The score is the sum
+    // of the uncovered numbers multiplied by the number just called, as in the puzzle
+    BoardWon {
+        board: usize,
+        rank: usize,
+        score: usize,
+    },
+}
+
+pub struct Game {
     numbers: Vec<usize>,
     boards: Vec<Bingo>,
 }
 
 impl Game {
-    // play the game, finds the winning board and returns the sum of the
-    // uncovered numbers in it, multiplied by the last number
-    fn play_to_win_first(&mut self) -> usize {
-        for &num in &self.numbers {
-            for board in &mut self.boards {
-                let has_won = board.check_win(num);
-                if has_won {
-                    return board.sum_uncovered() * num;
-                }
-            }
+    pub fn new(numbers: Vec<usize>, boards: Vec<Bingo>) -> Game {
+        Game { numbers, boards }
+    }
+
+    // plays the game, producing events as it goes. Boards that have won are not marked any more
+    pub fn play(self, rules: Rules) -> Play {
+        Play {
+            game: self,
+            rules,
+            next_number: 0,
+            winners: 0,
+            pending: VecDeque::new(),
         }
-        panic!("no winner after all numbers called");
     }
 
-    fn play_to_win_last(&mut self) -> usize {
-        let mut remaining = self.boards.len();
-        for &num in &self.numbers {
-            let one_left = remaining == 1;
-            for board in &mut self.boards {
-                if !board.has_won {
-                    let has_won = board.check_win(num);
-                    if has_won {
-                        if one_left {
-                            return board.sum_uncovered() * num;
-                        }
-                        remaining -= 1;
-                    }
+    // the score of the board that wins in the given place (counting from 1), if there is one
+    pub fn nth_winner_score(self, rank: usize, rules: Rules) -> Option<usize> {
+        self.play(rules).find_map(|event| match event {
+            Event::BoardWon {
+                rank: won_rank,
+                score,
+                ..
+            } if won_rank == rank => Some(score),
+            _ => None,
+        })
+    }
+
+    pub fn num_boards(&self) -> usize {
+        self.boards.len()
+    }
+}
+
+// the stream of events from playing a game
+pub struct Play {
+    game: Game,
+    rules: Rules,
+    next_number: usize,
+    winners: usize,
+    pending: VecDeque<Event>,
+}
+
+impl Play {
+    fn call_next(&mut self) -> bool {
+        let number = match self.game.numbers.get(self.next_number) {
+            Some(&number) => number,
+            None => return false,
+        };
+        self.next_number += 1;
+        self.pending.push_back(Event::NumberCalled { number });
+        for (index, board) in self.game.boards.iter_mut().enumerate() {
+            if board.has_won {
+                continue;
+            }
+            if let Some((row, col)) = board.check_number(number) {
+                self.pending.push_back(Event::BoardMarked {
+                    board: index,
+                    row,
+                    col,
+                });
+                if board.completes_line(row, col, &self.rules) {
+                    board.has_won = true;
+                    self.winners += 1;
+                    self.pending.push_back(Event::BoardWon {
+                        board: index,
+                        rank: self.winners,
+                        score: board.sum_uncovered() * number,
+                    });
                 }
             }
         }
-        panic!("couldn't find a last winner!");
+        true
     }
 }
 
-fn parse_bingo_board(v: &Vec<&str>) -> Bingo {
-    let board = v
-        .iter()
-        .map(|s| {
-            s.split_whitespace()
-                .map(|v| v.parse().unwrap())
-                .collect::<Vec<usize>>()
-                .try_into()
-                .unwrap()
-        })
-        .collect::<Vec<[usize; 5]>>()
-        .try_into()
-        .unwrap();
-    Bingo {
-        board,
-        covered: vec![],
-        has_won: false,
+impl Iterator for Play {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        if self.pending.is_empty() && !self.call_next() {
+            return None;
+        }
+        self.pending.pop_front()
     }
 }
 
+fn parse_board_row(input: Input) -> ParseResult<Vec<usize>> {
+    // numbers are lined up in columns, so there can be spaces before the first one
+    let (_, input) = optional(spaces)(input)?;
+    separated(unsigned, spaces)(input)
+}
+
+fn parse_game(input: Input) -> ParseResult<Game> {
+    let (numbers, input) = section(separated(unsigned, literal(",")))(input)?;
+    let (boards, input) = sections(try_map(lines(parse_board_row), Bingo::new))(input)?;
+    Ok((Game::new(numbers, boards), input))
+}
+
 fn read_file() -> Game {
     let mut file = File::open("./input/input4.txt").unwrap();
     let mut contents = String::new();
     file.read_to_string(&mut contents).unwrap();
-    let parts: Vec<Vec<&str>> = contents
-        .lines()
-        .group_by(|s| s.is_empty())
-        .into_iter()
-        .filter(|(k, _)| !k)
-        .map(|(_, g)| g.collect())
-        .collect();
-    let numbers: Vec<usize> = parts[0][0]
-        .split(",")
-        .map(|num| num.parse().unwrap())
-        .collect();
-    let boards = parts[1..].iter().map(parse_bingo_board).collect();
-    Game { numbers, boards }
-}
-
-fn solve_part_1(mut game: Game) -> usize {
-    game.play_to_win_first()
-}
-
-fn solve_part_2(mut game: Game) -> usize {
-    game.play_to_win_last()
+    parse_all(&contents, parse_game).unwrap()
+}
+
+fn solve_part_1(game: Game) -> usize {
+    game.nth_winner_score(1, Rules::default())
+        .expect("no winner after all numbers called")
+}
+
+fn solve_part_2(game: Game) -> usize {
+    let last = game.num_boards();
+    game.nth_winner_score(last, Rules::default())
+        .expect("couldn't find a last winner!")
 }
 
 pub fn part_1() -> usize {
@@ -1,132 +1,410 @@
-use std::collections::HashMap;
+use super::geometry::{Box2, Point2};
+use super::parse::{lines, literal, parse_all, signed, Input, ParseResult};
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
+use std::io;
 use std::io::prelude::*;
 
-#[derive(Copy, Clone)]
-struct Point {
-    x: isize,
-    y: isize,
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// the lattice points on a line, from start to end, generated one at a time
+pub struct LinePoints {
+    next: Point2,
+    step: Point2,
+    remaining: usize,
 }
 
-impl Point {
-    fn new(x: isize, y: isize) -> Point {
-        Point { x, y }
+impl Iterator for LinePoints {
+    type Item = Point2;
+
+    fn next(&mut self) -> Option<Point2> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let point = self.next;
+        self.next += self.step;
+        self.remaining -= 1;
+        Some(point)
     }
+}
 
-    fn line_to(&self, other: &Point) -> Vec<Point> {
-        let mut points = vec![];
-        let x_diff = other.x - self.x;
-        let y_diff = other.y - self.y;
+// identifies the infinite line a line lies on - see Line::carrier
+type Carrier = (isize, isize, i128);
 
-        let x_step = if x_diff > 0 {
-            1
-        } else if x_diff < 0 {
-            -1
+// a line between two points, of any slope. The lattice points on it are exactly
+// start + n * step for n from 0 to steps, where step is the difference between the ends
+// divided through by its gcd.
+// A line with both ends the same is treated as a horizontal line of length 0.
+#[derive(Copy, Clone, Debug)]
+pub struct Line {
+    start: Point2,
+    end: Point2,
+    step: Point2,
+    steps: usize,
+}
+
+impl Line {
+    pub fn new(start: Point2, end: Point2) -> Line {
+        let x_diff = end.x - start.x;
+        let y_diff = end.y - start.y;
+        let steps = gcd(x_diff.unsigned_abs(), y_diff.unsigned_abs());
+        let step = if steps == 0 {
+            Point2::new(1, 0)
         } else {
-            0
+            Point2::new(x_diff / steps as isize, y_diff / steps as isize)
         };
+        Line {
+            start,
+            end,
+            step,
+            steps,
+        }
+    }
+
+    pub fn start(&self) -> Point2 {
+        self.start
+    }
+
+    pub fn end(&self) -> Point2 {
+        self.end
+    }
 
-        let y_step = if y_diff > 0 {
-            1
-        } else if y_diff < 0 {
-            -1
+    pub fn is_axis_aligned(&self) -> bool {
+        self.start.x == self.end.x || self.start.y == self.end.y
+    }
+
+    pub fn points(&self) -> LinePoints {
+        LinePoints {
+            next: self.start,
+            step: self.step,
+            remaining: self.steps + 1,
+        }
+    }
+
+    // the step with its sign fixed, so that lines along the same direction agree on it
+    fn direction(&self) -> Point2 {
+        if self.step.x < 0 || (self.step.x == 0 && self.step.y < 0) {
+            -self.step
         } else {
-            0
-        };
+            self.step
+        }
+    }
 
-        let mut current_x = self.x;
-        let mut current_y = self.y;
-        loop {
-            points.push(Point::new(current_x, current_y));
-            if current_x == other.x {
-                if current_y == other.y {
-                    break;
-                }
-            }
-            current_x += x_step;
-            current_y += y_step;
+    // identifies the infinite line this lies on: the direction, plus the cross product of that
+    // with any point on the line (which is the same for all of them)
+    fn carrier(&self) -> Carrier {
+        let direction = self.direction();
+        (direction.x, direction.y, cross(direction, self.start))
+    }
+
+    // the range of the line's points, measured along its direction by the dot product with
+    // it. Points on the same carrier are a multiple of the direction's squared length apart
+    fn extent(&self) -> (i128, i128) {
+        let direction = self.direction();
+        let (a, b) = (dot(direction, self.start), dot(direction, self.end));
+        (a.min(b), a.max(b))
+    }
+
+    fn contains_step(&self, n: i128) -> bool {
+        n >= 0 && n <= self.steps as i128
+    }
+
+    // the lattice point where this crosses a line that isn't parallel to it, if there is one
+    fn crossing(&self, other: &Line) -> Option<Point2> {
+        let denominator = cross(self.step, other.step);
+        if denominator == 0 {
+            return None;
+        }
+        let offset = other.start - self.start;
+        let this_numerator = cross(offset, other.step);
+        let other_numerator = cross(offset, self.step);
+        if this_numerator % denominator != 0 || other_numerator % denominator != 0 {
+            return None;
+        }
+        let (n, m) = (this_numerator / denominator, other_numerator / denominator);
+        if !self.contains_step(n) || !other.contains_step(m) {
+            return None;
         }
+        Some(self.start + self.step * n as isize)
+    }
+
+    fn min_x(&self) -> isize {
+        self.start.x.min(self.end.x)
+    }
 
-        points
+    fn max_x(&self) -> isize {
+        self.start.x.max(self.end.x)
     }
 }
 
-// start and end of course determine the line on their own, but we add other info that is
-// calculated during construction, to avoid having to recalculate it later.
-// (And then removed start and end to eliminate compiler warnings as they were unused in the end!)
-struct Line {
-    x_diff: isize,
-    y_diff: isize,
-    points: Vec<Point>,
+fn cross(a: Point2, b: Point2) -> i128 {
+    a.x as i128 * b.y as i128 - a.y as i128 * b.x as i128
 }
 
-impl Line {
-    fn new(start: Point, end: Point) -> Line {
-        let x_diff = end.x - start.x;
-        let y_diff = end.y - start.y;
+fn dot(a: Point2, b: Point2) -> i128 {
+    a.x as i128 * b.x as i128 + a.y as i128 * b.y as i128
+}
 
-        Line {
-            x_diff,
-            y_diff,
-            points: start.line_to(&end),
+// counts the points covered by at least two of the lines, without ever listing the points
+// on each line - so it takes the same time and memory however long the lines are.
+// Lines lying on the same infinite line are dealt with by a sweep along it, finding the
+// stretches covered at least twice. All other overlaps are single points where two lines
+// cross, which are found by sweeping across the x axis and checking each pair of lines whose
+// x ranges overlap.
+pub fn count_overlaps(lines: &[Line]) -> usize {
+    let mut by_carrier: HashMap<Carrier, Vec<usize>> = HashMap::new();
+    for (index, line) in lines.iter().enumerate() {
+        by_carrier.entry(line.carrier()).or_default().push(index);
+    }
+
+    // for each carrier, the stretches (in terms of extent) covered by at least two lines
+    let mut multiple_stretches: HashMap<Carrier, Vec<(i128, i128)>> = HashMap::new();
+    let mut total = 0;
+    for (&carrier, indices) in &by_carrier {
+        let (dx, dy, _) = carrier;
+        let step = Point2::new(dx, dy);
+        let spacing = dot(step, step);
+        let mut events = vec![];
+        for &index in indices {
+            let (from, to) = lines[index].extent();
+            events.push((from, 1));
+            events.push((to + spacing, -1));
         }
+        events.sort_unstable();
+        let mut coverage = 0;
+        let mut stretches = vec![];
+        for (i, &(position, change)) in events.iter().enumerate() {
+            coverage += change;
+            if coverage >= 2 {
+                let next = events[i + 1].0;
+                if next > position {
+                    total += ((next - position) / spacing) as usize;
+                    stretches.push((position, next - spacing));
+                }
+            }
+        }
+        multiple_stretches.insert(carrier, stretches);
+    }
+
+    let already_counted = |line: &Line, point: Point2| {
+        let position = dot(line.direction(), point);
+        multiple_stretches[&line.carrier()]
+            .iter()
+            .any(|&(from, to)| from <= position && position <= to)
+    };
+
+    let mut order: Vec<usize> = (0..lines.len()).collect();
+    order.sort_unstable_by_key(|&index| lines[index].min_x());
+    // every crossing point found, along with all the lines through it
+    let mut crossings: HashMap<Point2, HashSet<usize>> = HashMap::new();
+    for (i, &index) in order.iter().enumerate() {
+        let line = &lines[index];
+        for &other_index in &order[i + 1..] {
+            let other = &lines[other_index];
+            if other.min_x() > line.max_x() {
+                break;
+            }
+            if let Some(point) = line.crossing(other) {
+                let through = crossings.entry(point).or_default();
+                through.insert(index);
+                through.insert(other_index);
+            }
+        }
+    }
+    // a crossing point may already have been counted, if it's covered twice by lines on the
+    // same carrier - and it may even have been counted more than once, if that happens on
+    // several carriers. Any line through the point is guaranteed to be in its list, as it
+    // can't be parallel to both of two lines which cross there
+    let mut total = total as isize;
+    for (&point, through) in &crossings {
+        let counted_carriers: HashSet<Carrier> = through
+            .iter()
+            .map(|&index| &lines[index])
+            .filter(|line| already_counted(line, point))
+            .map(|line| line.carrier())
+            .collect();
+        total += match counted_carriers.len() {
+            0 => 1,
+            times => 1 - times as isize,
+        };
     }
+    total as usize
 }
 
-struct PointsOnLines {
-    counts: HashMap<(isize, isize), usize>,
+// counts every point on the lines individually. Much slower than count_overlaps for long
+// lines, but gives the whole picture of the vents
+pub struct PointsOnLines {
+    counts: HashMap<Point2, usize>,
 }
 
 impl PointsOnLines {
-    fn new() -> PointsOnLines {
+    pub fn new() -> PointsOnLines {
         PointsOnLines {
             counts: HashMap::new(),
         }
     }
 
-    fn add(&mut self, points: Vec<Point>) {
-        for point in points {
-            let key = (point.x, point.y);
-            let current = self.counts.get_mut(&key);
-            match current {
-                Some(val) => *val += 1,
-                None => {
-                    self.counts.insert(key, 1);
-                }
-            }
+    pub fn add(&mut self, line: &Line) {
+        for point in line.points() {
+            *self.counts.entry(point).or_insert(0) += 1;
         }
     }
 
-    fn count_greater_than_one(&self) -> usize {
+    pub fn count_greater_than_one(&self) -> usize {
         self.counts.iter().filter(|(_, &val)| val > 1).count()
     }
+
+    pub fn count_at(&self, point: &Point2) -> usize {
+        *self.counts.get(point).unwrap_or(&0)
+    }
+
+    // the smallest box containing every point on a line, if there are any
+    pub fn bounds(&self) -> Option<Box2> {
+        Box2::bounding(self.counts.keys().copied())
+    }
+
+    // the area to draw: the given crop, or else everything. None if the crop's min is past its max
+    // on either axis, so that there's nothing in it
+    fn area(&self, crop: Option<Box2>) -> Option<Box2> {
+        match crop {
+            Some(crop) => Box2::new(crop.min, crop.max),
+            None => Some(self.bounds().unwrap_or_default()),
+        }
+    }
+
+    // as above, but an empty crop is an error, as there's no such thing as an image with no
+    // pixels to write
+    fn image_area(&self, crop: Option<Box2>) -> io::Result<Box2> {
+        self.area(crop)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "the crop box is empty"))
+    }
+
+    // draws the counts in the same format as the puzzle description: a . for no lines, and
+    // otherwise the number of lines (or + if there are more than 9)
+    pub fn render_ascii(&self, crop: Option<Box2>) -> String {
+        let area = match self.area(crop) {
+            Some(area) => area,
+            None => return String::new(),
+        };
+        let mut diagram = String::new();
+        for y in area.min.y..=area.max.y {
+            for x in area.min.x..=area.max.x {
+                let c = match self.count_at(&Point2::new(x, y)) {
+                    0 => '.',
+                    count if count > 9 => '+',
+                    count => char::from_digit(count as u32, 10).unwrap(),
+                };
+                diagram.push(c);
+            }
+            diagram.push('\n');
+        }
+        diagram
+    }
+
+    // writes the counts as a binary PGM image, with brightness proportional to the count
+    pub fn write_pgm<W: Write>(&self, out: &mut W, crop: Option<Box2>) -> io::Result<()> {
+        let area = self.image_area(crop)?;
+        let max = self.max_count(&area);
+        let size = area.size();
+        let (width, height) = (size.x as usize, size.y as usize);
+        write!(out, "P5\n{} {}\n255\n", width, height)?;
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in area.min.y..=area.max.y {
+            for x in area.min.x..=area.max.x {
+                let count = self.count_at(&Point2::new(x, y));
+                pixels.push((count * 255 / max) as u8);
+            }
+        }
+        out.write_all(&pixels)
+    }
+
+    // writes the counts as a binary PPM image, coloured on a scale running from black for
+    // no lines, through blue and red, to yellow for the highest count
+    pub fn write_ppm<W: Write>(&self, out: &mut W, crop: Option<Box2>) -> io::Result<()> {
+        let area = self.image_area(crop)?;
+        let max = self.max_count(&area);
+        let size = area.size();
+        let (width, height) = (size.x as usize, size.y as usize);
+        write!(out, "P6\n{} {}\n255\n", width, height)?;
+        let mut pixels = Vec::with_capacity(3 * width * height);
+        for y in area.min.y..=area.max.y {
+            for x in area.min.x..=area.max.x {
+                let count = self.count_at(&Point2::new(x, y));
+                pixels.extend(heat_colour(count, max));
+            }
+        }
+        out.write_all(&pixels)
+    }
+
+    // never 0, so it's always safe to divide by
+    fn max_count(&self, area: &Box2) -> usize {
+        self.counts
+            .iter()
+            .filter(|(point, _)| area.contains(point))
+            .map(|(_, &count)| count)
+            .max()
+            .unwrap_or(1)
+    }
 }
 
-fn parse_point(s: &str) -> Point {
-    let coords: Vec<isize> = s.split(",").map(|num| num.parse().unwrap()).collect();
-    Point::new(coords[0], coords[1])
+fn heat_colour(count: usize, max: usize) -> [u8; 3] {
+    const SCALE: [[u8; 3]; 4] = [[0, 0, 0], [0, 0, 255], [255, 0, 0], [255, 255, 0]];
+    if count == 0 {
+        return SCALE[0];
+    }
+    if max == 1 {
+        return SCALE[3];
+    }
+    // interpolate along the scale, with a count of 1 at the blue end and max at the yellow end
+    let position = (count - 1) as f64 / (max - 1) as f64 * 2.0;
+    let index = (position.floor() as usize).min(1);
+    let fraction = position - index as f64;
+    let (from, to) = (SCALE[index + 1], SCALE[index + 2]);
+    let mut colour = [0; 3];
+    for channel in 0..3 {
+        let start = from[channel] as f64;
+        let end = to[channel] as f64;
+        colour[channel] = (start + (end - start) * fraction).round() as u8;
+    }
+    colour
 }
 
-fn parse_line(s: &str) -> Line {
-    let parts: Vec<&str> = s.split_ascii_whitespace().collect();
-    let start_point = parse_point(parts[0]);
-    let end_point = parse_point(parts[2]);
-    Line::new(start_point, end_point)
+impl Default for PointsOnLines {
+    fn default() -> PointsOnLines {
+        PointsOnLines::new()
+    }
+}
+
+fn parse_point(input: Input) -> ParseResult<Point2> {
+    let (x, input) = signed(input)?;
+    let (_, input) = literal(",")(input)?;
+    let (y, input) = signed(input)?;
+    Ok((Point2::new(x, y), input))
+}
+
+fn parse_line(input: Input) -> ParseResult<Line> {
+    let (start_point, input) = parse_point(input)?;
+    let (_, input) = literal(" -> ")(input)?;
+    let (end_point, input) = parse_point(input)?;
+    Ok((Line::new(start_point, end_point), input))
 }
 
 fn read_file() -> Vec<Line> {
     let mut file = File::open("./input/input5.txt").unwrap();
     let mut contents = String::new();
     file.read_to_string(&mut contents).unwrap();
-    contents.lines().map(parse_line).collect()
+    parse_all(&contents, lines(parse_line)).unwrap()
 }
 
 fn solve_part_1(lines: Vec<Line>) -> usize {
-    let mut counts = PointsOnLines::new();
-    for line in lines.iter().filter(|l| l.x_diff == 0 || l.y_diff == 0) {
-        counts.add(line.points.iter().map(|p| p.clone()).collect());
-    }
-    counts.count_greater_than_one()
+    let straight: Vec<Line> = lines.into_iter().filter(Line::is_axis_aligned).collect();
+    count_overlaps(&straight)
 }
 
 pub fn part_1() -> usize {
@@ -135,11 +413,7 @@ pub fn part_1() -> usize {
 }
 
 fn solve_part_2(lines: Vec<Line>) -> usize {
-    let mut counts = PointsOnLines::new();
-    for line in lines.iter() {
-        counts.add(line.points.iter().map(|p| p.clone()).collect());
-    }
-    counts.count_greater_than_one()
+    count_overlaps(&lines)
 }
 
 pub fn part_2() -> usize {
@@ -1,48 +1,102 @@
-use std::collections::HashMap;
+use super::matrix::{Arithmetic, Exact, Matrix};
+use super::parse::{literal, parse_all, separated, try_map, unsigned};
+use num_bigint::BigUint;
 use std::fs::File;
 use std::io::prelude::*;
 
-struct Fish {
-    number_of_each: HashMap<usize, usize>,
+// the timer a fish goes back to after giving birth, and the timer a newborn fish starts with
+#[derive(Clone, Copy)]
+pub struct Lifecycle {
+    pub reset_timer: usize,
+    pub newborn_timer: usize,
 }
 
-impl Fish {
-    fn new(nums: Vec<usize>) -> Fish {
-        let mut number_of_each = HashMap::new();
-        for num in nums {
-            let val = number_of_each.entry(num).or_insert(0);
-            *val += 1;
+impl Lifecycle {
+    pub fn standard() -> Lifecycle {
+        Lifecycle {
+            reset_timer: 6,
+            newborn_timer: 8,
         }
-        Fish { number_of_each }
     }
 
-    fn evolve(&mut self) {
-        // reproduction rules are equivalent to the following in this setting:
-        // the new value of n is equal to the old value of n + 1 (for n from 0 to 7)
-        // there is one exception to the above - the new value of 6 is the sum of the
-        // just-calculated value and the previous value of 0
-        // the new value of 8 is equal to the old value of 0
-        let mut temp_new: HashMap<usize, usize> = HashMap::new();
-        for n in 0..8 {
-            temp_new.insert(n, *self.number_of_each.get(&(n + 1)).unwrap_or(&0));
-        }
-        let zeros = *self.number_of_each.get(&0).unwrap_or(&0);
-        let six_value = temp_new.entry(6).or_insert(0);
-        *six_value += zeros;
-        temp_new.insert(8, zeros);
-        for n in 0..9 {
-            self.number_of_each.insert(n, *temp_new.get(&n).unwrap());
+    fn num_timers(&self) -> usize {
+        self.reset_timer.max(self.newborn_timer) + 1
+    }
+}
+
+pub struct Fish {
+    lifecycle: Lifecycle,
+    // the number of fish with each timer value
+    number_of_each: Vec<u64>,
+}
+
+impl Fish {
+    // fails if any fish starts with a timer higher than the lifecycle allows
+    pub fn new(timers: Vec<usize>, lifecycle: Lifecycle) -> Result<Fish, String> {
+        let mut number_of_each = vec![0; lifecycle.num_timers()];
+        for timer in timers {
+            if timer >= number_of_each.len() {
+                return Err(format!(
+                    "fish timer {} is too large for the lifecycle",
+                    timer
+                ));
+            }
+            number_of_each[timer] += 1;
         }
+        Ok(Fish {
+            lifecycle,
+            number_of_each,
+        })
     }
 
-    fn evolve_days(&mut self, days: usize) {
-        for _ in 1..(days + 1) {
-            self.evolve();
+    // one day is a linear map on the timer counts: every timer decreases by 1, and the fish
+    // at 0 move to the reset timer while the same number of newborns appear
+    fn day_matrix<A: Arithmetic>(&self, arith: &A) -> Matrix<A::Value> {
+        let size = self.lifecycle.num_timers();
+        let mut matrix = Matrix::zero(size, arith);
+        for timer in 1..size {
+            matrix.set(timer - 1, timer, arith.one());
         }
+        let Lifecycle {
+            reset_timer,
+            newborn_timer,
+        } = self.lifecycle;
+        matrix.set(reset_timer, 0, arith.one());
+        let newborns = arith.add(matrix.get(newborn_timer, 0), &arith.one());
+        matrix.set(newborn_timer, 0, newborns);
+        matrix
+    }
+
+    // the number of fish with each timer value after the given number of days. Takes
+    // O(log days) matrix multiplications, so works for huge numbers of days
+    pub fn timers_after<A: Arithmetic>(&self, days: u64, arith: &A) -> Vec<A::Value> {
+        let start: Vec<A::Value> = self
+            .number_of_each
+            .iter()
+            .map(|&n| arith.number(n))
+            .collect();
+        self.day_matrix(arith).pow(days, arith).apply(&start, arith)
     }
 
-    fn total(&self) -> usize {
-        self.number_of_each.values().sum()
+    pub fn total_after<A: Arithmetic>(&self, days: u64, arith: &A) -> A::Value {
+        self.timers_after(days, arith)
+            .iter()
+            .fold(arith.zero(), |total, count| arith.add(&total, count))
+    }
+
+    // the total population at the start and after each day up to the given one, stepping
+    // through every day
+    pub fn population_series(&self, days: usize) -> Vec<BigUint> {
+        let mut current: Vec<BigUint> = self.number_of_each.iter().map(|&n| n.into()).collect();
+        let mut series = vec![current.iter().sum()];
+        for _ in 0..days {
+            let zeros = current.remove(0);
+            current.push(BigUint::from(0u32));
+            current[self.lifecycle.reset_timer] += &zeros;
+            current[self.lifecycle.newborn_timer] += &zeros;
+            series.push(current.iter().sum());
+        }
+        series
     }
 }
 
@@ -50,29 +104,28 @@ fn read_file() -> Fish {
     let mut file = File::open("./input/input6.txt").unwrap();
     let mut contents = String::new();
     file.read_to_string(&mut contents).unwrap();
-    let days = contents
-        .split(",")
-        .map(|digit| digit.parse().unwrap())
-        .collect();
-    Fish::new(days)
+    let timers = separated(unsigned, literal(","));
+    parse_all(
+        &contents,
+        try_map(timers, |timers| Fish::new(timers, Lifecycle::standard())),
+    )
+    .unwrap()
 }
 
-fn solve_part_1(fish: &mut Fish) -> usize {
-    fish.evolve_days(80);
-    fish.total()
+fn solve_part_1(fish: &Fish) -> BigUint {
+    fish.total_after(80, &Exact)
 }
 
-pub fn part_1() -> usize {
-    let mut fish = read_file();
-    solve_part_1(&mut fish)
+pub fn part_1() -> BigUint {
+    let fish = read_file();
+    solve_part_1(&fish)
 }
 
-fn solve_part_2(fish: &mut Fish) -> usize {
-    fish.evolve_days(256);
-    fish.total()
+fn solve_part_2(fish: &Fish) -> BigUint {
+    fish.total_after(256, &Exact)
 }
 
-pub fn part_2() -> usize {
-    let mut fish = read_file();
-    solve_part_2(&mut fish)
+pub fn part_2() -> BigUint {
+    let fish = read_file();
+    solve_part_2(&fish)
 }
@@ -1,3 +1,4 @@
+use super::parse::{lines, literal, map, parse_all, take_while, Input, ParseResult};
 use std::convert::TryInto;
 use std::fs::File;
 use std::io::prelude::*;
@@ -197,42 +198,59 @@ struct DisplayData {
     data: Vec<SegmentData>,
 }
 
-fn parse_segment(c: &char) -> Segment {
+fn parse_segment(c: char) -> Option<Segment> {
     match c {
-        'a' => Segment::A,
-        'b' => Segment::B,
-        'c' => Segment::C,
-        'd' => Segment::D,
-        'e' => Segment::E,
-        'f' => Segment::F,
-        'g' => Segment::G,
-        _ => panic!("unexpected character: {}", c),
+        'a' => Some(Segment::A),
+        'b' => Some(Segment::B),
+        'c' => Some(Segment::C),
+        'd' => Some(Segment::D),
+        'e' => Some(Segment::E),
+        'f' => Some(Segment::F),
+        'g' => Some(Segment::G),
+        _ => None,
     }
 }
 
-fn parse_display(s: &str) -> Display {
-    let segments = s.chars().map(|c| parse_segment(&c)).collect();
-    Display { segments }
+fn parse_display(input: Input) -> ParseResult<Display> {
+    let letters = take_while("a segment", |c| parse_segment(c).is_some());
+    map(letters, |letters| {
+        let segments = letters.chars().filter_map(parse_segment).collect();
+        Display { segments }
+    })(input)
 }
 
-fn parse_displays(s: &str) -> Vec<Display> {
-    s.split(" ").map(parse_display).collect()
+// exactly N displays with spaces between them. The number has to be known up front, since the
+// bar between the two lists of displays has a space before it too
+fn parse_displays<const N: usize>(mut input: Input) -> ParseResult<[Display; N]> {
+    let mut displays = vec![];
+    for index in 0..N {
+        if index > 0 {
+            input = literal(" ")(input)?.1;
+        }
+        let (display, rest) = parse_display(input)?;
+        displays.push(display);
+        input = rest;
+    }
+    let displays = displays.try_into().unwrap_or_else(|_| unreachable!());
+    Ok((displays, input))
 }
 
-fn parse_line(s: &str) -> SegmentData {
-    let parts: Vec<&str> = s.split(" | ").collect();
-    let input_str = parts[0];
-    let output_str = parts[1];
-    let input = parse_displays(input_str).try_into().unwrap();
-    let output = parse_displays(output_str).try_into().unwrap();
-    SegmentData { input, output }
+fn parse_line(input: Input) -> ParseResult<SegmentData> {
+    let (input_displays, input) = parse_displays(input)?;
+    let (_, input) = literal(" | ")(input)?;
+    let (output, input) = parse_displays(input)?;
+    let data = SegmentData {
+        input: input_displays,
+        output,
+    };
+    Ok((data, input))
 }
 
 fn read_file() -> DisplayData {
     let mut file = File::open("./input/input8.txt").unwrap();
     let mut contents = String::new();
     file.read_to_string(&mut contents).unwrap();
-    let data = contents.lines().map(parse_line).collect();
+    let data = parse_all(&contents, lines(parse_line)).unwrap();
     DisplayData { data }
 }
 
@@ -1,11 +1,25 @@
+use super::parse::{digit, grid, parse_all, try_map};
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
 
-struct HeightMap {
+pub struct HeightMap {
     heights: Vec<Vec<u8>>,
 }
 
 impl HeightMap {
+    // fails if the map is empty or not rectangular
+    pub fn new(heights: Vec<Vec<u8>>) -> Result<HeightMap, String> {
+        let width = heights.first().map_or(0, |row| row.len());
+        if width == 0 {
+            return Err("the height map is empty".to_owned());
+        }
+        if heights.iter().any(|row| row.len() != width) {
+            return Err("the height map rows are not all the same length".to_owned());
+        }
+        Ok(HeightMap { heights })
+    }
+
     fn get_value(&self, row: usize, col: usize) -> u8 {
         self.heights[row][col]
     }
@@ -67,38 +81,145 @@ impl HeightMap {
         lows
     }
 
-    // get the basin (again as a vector of pairs of coordinates and value) containing
-    // a particular point.
-    // This function is the entry point, it uses get_basin_recursive to do the "hard work!"
-    fn get_basin(&self, row: usize, col: usize) -> Vec<((usize, usize), u8)> {
-        let value = self.get_value(row, col);
-        self.get_basin_recursive(row, col, vec![((row, col), value)])
-    }
+    // finds every basin - that is, every connected area of points that aren't 9s.
+    // Rather than flooding out from each low point, we go through the map once joining each
+    // point to its neighbours to the right and below (unless either is a 9) in a union-find
+    // structure, so that each basin ends up as one set. This works whatever the basins look
+    // like, even if one has several equally low points or none that's strictly lower than all
+    // its neighbours.
+    // The basins are returned in the order of their first point, reading row by row
+    pub fn basins(&self) -> Vec<Basin> {
+        let height = self.get_height();
+        let width = self.get_width();
+        let index = |row: usize, col: usize| row * width + col;
+        let mut sets = UnionFind::new(width * height);
+        for row in 0..height {
+            for col in 0..width {
+                if self.get_value(row, col) == 9 {
+                    continue;
+                }
+                if col + 1 < width && self.get_value(row, col + 1) != 9 {
+                    sets.union(index(row, col), index(row, col + 1));
+                }
+                if row + 1 < height && self.get_value(row + 1, col) != 9 {
+                    sets.union(index(row, col), index(row + 1, col));
+                }
+            }
+        }
 
-    fn get_basin_recursive(
-        &self,
-        row: usize,
-        col: usize,
-        basin: Vec<((usize, usize), u8)>,
-    ) -> Vec<((usize, usize), u8)> {
-        let mut new_basin = basin.clone();
-        let value = self.get_value(row, col);
-        let neighbours = self.get_neighbours(row, col);
-        for (neighbour_row, neighbour_col) in neighbours {
-            let neighbour_value = self.get_value(neighbour_row, neighbour_col);
-            // stop if we find a 9, or an equal or lower value
-            if neighbour_value != 9 && neighbour_value > value {
-                // don't add the point if we've already been here
-                if let None = new_basin
-                    .iter()
-                    .find(|((row_, col_), _)| row_ == &neighbour_row && col_ == &neighbour_col)
-                {
-                    new_basin.push(((neighbour_row, neighbour_col), neighbour_value));
+        let mut basins: Vec<Basin> = vec![];
+        // the position in basins of the basin each set has been made into
+        let mut basin_of_set = HashMap::new();
+        for row in 0..height {
+            for col in 0..width {
+                let value = self.get_value(row, col);
+                if value == 9 {
+                    continue;
+                }
+                let set = sets.find(index(row, col));
+                let label = *basin_of_set.entry(set).or_insert_with(|| {
+                    basins.push(Basin {
+                        label: basins.len(),
+                        low_points: vec![],
+                        lowest: value,
+                        cells: vec![],
+                    });
+                    basins.len() - 1
+                });
+                let basin = &mut basins[label];
+                basin.cells.push((row, col));
+                if value < basin.lowest {
+                    basin.lowest = value;
+                    basin.low_points.clear();
+                }
+                if value == basin.lowest {
+                    basin.low_points.push((row, col));
                 }
-                new_basin = self.get_basin_recursive(neighbour_row, neighbour_col, new_basin);
             }
         }
-        new_basin
+        basins
+    }
+
+    // draws the map with each basin's points shown by a character for its label (cycling
+    // through letters and digits if there are more basins than that) and 9s shown as #
+    pub fn render_basins(&self, basins: &[Basin]) -> String {
+        const LABELS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+        let mut grid = vec![vec!['#'; self.get_width()]; self.get_height()];
+        for basin in basins {
+            let label = LABELS[basin.label % LABELS.len()] as char;
+            for &(row, col) in &basin.cells {
+                grid[row][col] = label;
+            }
+        }
+        let mut map = String::new();
+        for row in grid {
+            map.extend(row);
+            map.push('\n');
+        }
+        map
+    }
+}
+
+#[derive(Debug)]
+pub struct Basin {
+    pub label: usize,
+    // all the points in the basin with its lowest height
+    pub low_points: Vec<(usize, usize)>,
+    pub lowest: u8,
+    pub cells: Vec<(usize, usize)>,
+}
+
+impl Basin {
+    pub fn size(&self) -> usize {
+        self.cells.len()
+    }
+
+    // the low point, if there is only one
+    pub fn low_point(&self) -> Option<(usize, usize)> {
+        match self.low_points[..] {
+            [point] => Some(point),
+            _ => None,
+        }
+    }
+}
+
+// keeps track of which of a collection of items have been joined together into sets
+struct UnionFind {
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(size: usize) -> UnionFind {
+        UnionFind {
+            parents: (0..size).collect(),
+            sizes: vec![1; size],
+        }
+    }
+
+    // the item representing the set this one is in. Each item visited on the way is pointed
+    // at its grandparent, to keep the paths short for next time
+    fn find(&mut self, item: usize) -> usize {
+        let mut current = item;
+        while self.parents[current] != current {
+            let grandparent = self.parents[self.parents[current]];
+            self.parents[current] = grandparent;
+            current = grandparent;
+        }
+        current
+    }
+
+    // joins the sets containing the two items, putting the smaller under the larger
+    fn union(&mut self, first: usize, second: usize) {
+        let (mut first, mut second) = (self.find(first), self.find(second));
+        if first == second {
+            return;
+        }
+        if self.sizes[first] < self.sizes[second] {
+            std::mem::swap(&mut first, &mut second);
+        }
+        self.parents[second] = first;
+        self.sizes[first] += self.sizes[second];
     }
 }
 
@@ -106,15 +227,7 @@ fn read_file() -> HeightMap {
     let mut file = File::open("./input/input9.txt").unwrap();
     let mut contents = String::new();
     file.read_to_string(&mut contents).unwrap();
-    let heights = contents
-        .lines()
-        .map(|line| {
-            line.chars()
-                .map(|n| n.to_string().parse::<u8>().unwrap())
-                .collect()
-        })
-        .collect();
-    HeightMap { heights }
+    parse_all(&contents, try_map(grid("a digit", digit), HeightMap::new)).unwrap()
 }
 
 fn solve_part_1(heights: HeightMap) -> usize {
@@ -126,11 +239,7 @@ fn solve_part_1(heights: HeightMap) -> usize {
 }
 
 fn solve_part_2(heights: HeightMap) -> usize {
-    let low_points = heights.get_lows();
-    let mut basin_sizes: Vec<usize> = low_points
-        .iter()
-        .map(|((row, col), _)| heights.get_basin(*row, *col).len())
-        .collect();
+    let mut basin_sizes: Vec<usize> = heights.basins().iter().map(Basin::size).collect();
     // sort in descending order
     basin_sizes.sort_unstable_by(|a, b| b.cmp(a));
     basin_sizes[0] * basin_sizes[1] * basin_sizes[2]
@@ -0,0 +1,176 @@
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+// points on a grid, in 2 or 3 dimensions. They double up as the vectors between points, so
+// they can be added and subtracted, and multiplied by a number
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Point2 {
+    pub x: isize,
+    pub y: isize,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Point3 {
+    pub x: isize,
+    pub y: isize,
+    pub z: isize,
+}
+
+impl Point2 {
+    pub fn new(x: isize, y: isize) -> Point2 {
+        Point2 { x, y }
+    }
+}
+
+impl Point3 {
+    pub fn new(x: isize, y: isize, z: isize) -> Point3 {
+        Point3 { x, y, z }
+    }
+}
+
+// a box of points with sides parallel to the axes, including the points on its edges. A box
+// whose max is less than its min on any axis would have no points in it, so rather than make
+// one of those, the constructors and intersection give None
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct Box2 {
+    pub min: Point2,
+    pub max: Point2,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct Box3 {
+    pub min: Point3,
+    pub max: Point3,
+}
+
+// everything that works the same way whatever the number of dimensions, written out once for
+// each pair of point and box types in terms of their co-ordinates
+macro_rules! geometry {
+    ($point:ident, $boxed:ident, $($axis:ident),+) => {
+        impl $point {
+            // the number of steps between two points, moving along one axis at a time
+            pub fn manhattan(&self, other: &$point) -> usize {
+                0 $(+ self.$axis.abs_diff(other.$axis))+
+            }
+
+            // the number of steps between two points, moving along any number of axes at once
+            pub fn chebyshev(&self, other: &$point) -> usize {
+                0 $(.max(self.$axis.abs_diff(other.$axis)))+
+            }
+        }
+
+        impl Add for $point {
+            type Output = $point;
+
+            fn add(self, other: $point) -> $point {
+                $point { $($axis: self.$axis + other.$axis),+ }
+            }
+        }
+
+        impl Sub for $point {
+            type Output = $point;
+
+            fn sub(self, other: $point) -> $point {
+                $point { $($axis: self.$axis - other.$axis),+ }
+            }
+        }
+
+        impl Neg for $point {
+            type Output = $point;
+
+            fn neg(self) -> $point {
+                $point { $($axis: -self.$axis),+ }
+            }
+        }
+
+        impl Mul<isize> for $point {
+            type Output = $point;
+
+            fn mul(self, factor: isize) -> $point {
+                $point { $($axis: self.$axis * factor),+ }
+            }
+        }
+
+        impl AddAssign for $point {
+            fn add_assign(&mut self, other: $point) {
+                *self = *self + other;
+            }
+        }
+
+        impl SubAssign for $point {
+            fn sub_assign(&mut self, other: $point) {
+                *self = *self - other;
+            }
+        }
+
+        impl $boxed {
+            // the box with the given corners, or None if min is past max on any axis
+            pub fn new(min: $point, max: $point) -> Option<$boxed> {
+                if true $(&& min.$axis <= max.$axis)+ {
+                    Some($boxed { min, max })
+                } else {
+                    None
+                }
+            }
+
+            // the box with any two opposite corners
+            pub fn from_corners(a: $point, b: $point) -> $boxed {
+                $boxed {
+                    min: $point { $($axis: a.$axis.min(b.$axis)),+ },
+                    max: $point { $($axis: a.$axis.max(b.$axis)),+ },
+                }
+            }
+
+            // the smallest box containing all the points, if there are any
+            pub fn bounding<I: IntoIterator<Item = $point>>(points: I) -> Option<$boxed> {
+                let mut points = points.into_iter();
+                let first = points.next()?;
+                Some(points.fold($boxed { min: first, max: first }, |bounds, point| {
+                    bounds.union_bounds(&$boxed { min: point, max: point })
+                }))
+            }
+
+            pub fn contains(&self, point: &$point) -> bool {
+                true $(&& self.min.$axis <= point.$axis && point.$axis <= self.max.$axis)+
+            }
+
+            // the points in both boxes, if there are any
+            pub fn intersection(&self, other: &$boxed) -> Option<$boxed> {
+                $boxed::new(
+                    $point { $($axis: self.min.$axis.max(other.min.$axis)),+ },
+                    $point { $($axis: self.max.$axis.min(other.max.$axis)),+ },
+                )
+            }
+
+            // the smallest box containing both boxes
+            pub fn union_bounds(&self, other: &$boxed) -> $boxed {
+                $boxed {
+                    min: $point { $($axis: self.min.$axis.min(other.min.$axis)),+ },
+                    max: $point { $($axis: self.max.$axis.max(other.max.$axis)),+ },
+                }
+            }
+
+            // the part of this box inside the given limits, if any of it is
+            pub fn clip(&self, limits: &$boxed) -> Option<$boxed> {
+                self.intersection(limits)
+            }
+
+            // the nearest point in the box to the given one
+            pub fn clamp(&self, point: &$point) -> $point {
+                $point { $($axis: point.$axis.clamp(self.min.$axis, self.max.$axis)),+ }
+            }
+
+            // the number of points in the box along each axis
+            pub fn size(&self) -> $point {
+                $point { $($axis: self.max.$axis - self.min.$axis + 1),+ }
+            }
+
+            // the number of points in the box
+            pub fn count(&self) -> u64 {
+                1 $(* (self.max.$axis - self.min.$axis + 1) as u64)+
+            }
+        }
+    };
+}
+
+geometry!(Point2, Box2, x, y);
+geometry!(Point3, Box3, x, y, z);
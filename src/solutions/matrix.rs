@@ -0,0 +1,174 @@
+use num_bigint::BigUint;
+
+// the number system a matrix computation is done in. This lets the same matrix code give
+// either exact answers (which may be very large) or answers modulo some number
+pub trait Arithmetic {
+    type Value: Clone;
+
+    fn zero(&self) -> Self::Value;
+    fn one(&self) -> Self::Value;
+    fn number(&self, n: u64) -> Self::Value;
+    fn add(&self, a: &Self::Value, b: &Self::Value) -> Self::Value;
+    fn mul(&self, a: &Self::Value, b: &Self::Value) -> Self::Value;
+    fn is_zero(&self, a: &Self::Value) -> bool;
+}
+
+// arbitrary-precision arithmetic
+pub struct Exact;
+
+impl Arithmetic for Exact {
+    type Value = BigUint;
+
+    fn zero(&self) -> BigUint {
+        BigUint::from(0u32)
+    }
+
+    fn one(&self) -> BigUint {
+        BigUint::from(1u32)
+    }
+
+    fn number(&self, n: u64) -> BigUint {
+        BigUint::from(n)
+    }
+
+    fn add(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a + b
+    }
+
+    fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a * b
+    }
+
+    fn is_zero(&self, a: &BigUint) -> bool {
+        a.bits() == 0
+    }
+}
+
+// arithmetic modulo the given number, which must not be 0
+pub struct Modular(pub u64);
+
+impl Arithmetic for Modular {
+    type Value = u64;
+
+    fn zero(&self) -> u64 {
+        0
+    }
+
+    fn one(&self) -> u64 {
+        1 % self.0
+    }
+
+    fn number(&self, n: u64) -> u64 {
+        n % self.0
+    }
+
+    fn add(&self, a: &u64, b: &u64) -> u64 {
+        ((*a as u128 + *b as u128) % self.0 as u128) as u64
+    }
+
+    fn mul(&self, a: &u64, b: &u64) -> u64 {
+        ((*a as u128 * *b as u128) % self.0 as u128) as u64
+    }
+
+    fn is_zero(&self, a: &u64) -> bool {
+        *a == 0
+    }
+}
+
+// a square matrix, stored row by row
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix<T> {
+    size: usize,
+    entries: Vec<T>,
+}
+
+impl<T: Clone> Matrix<T> {
+    pub fn zero<A: Arithmetic<Value = T>>(size: usize, arith: &A) -> Matrix<T> {
+        Matrix {
+            size,
+            entries: vec![arith.zero(); size * size],
+        }
+    }
+
+    pub fn identity<A: Arithmetic<Value = T>>(size: usize, arith: &A) -> Matrix<T> {
+        let mut matrix = Matrix::zero(size, arith);
+        for i in 0..size {
+            matrix.set(i, i, arith.one());
+        }
+        matrix
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> &T {
+        &self.entries[row * self.size + col]
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, value: T) {
+        self.entries[row * self.size + col] = value;
+    }
+
+    pub fn mul<A: Arithmetic<Value = T>>(&self, other: &Matrix<T>, arith: &A) -> Matrix<T> {
+        assert_eq!(
+            self.size, other.size,
+            "can't multiply matrices of different sizes"
+        );
+        let mut result = Matrix::zero(self.size, arith);
+        for row in 0..self.size {
+            for k in 0..self.size {
+                let left = self.get(row, k);
+                // the matrices we deal with are mostly zeros, so this saves a lot of work
+                if arith.is_zero(left) {
+                    continue;
+                }
+                for col in 0..self.size {
+                    let right = other.get(k, col);
+                    if arith.is_zero(right) {
+                        continue;
+                    }
+                    let product = arith.mul(left, right);
+                    let entry = &mut result.entries[row * self.size + col];
+                    *entry = arith.add(entry, &product);
+                }
+            }
+        }
+        result
+    }
+
+    // computes the matrix to the given power by repeated squaring, so only takes
+    // O(log exponent) multiplications
+    pub fn pow<A: Arithmetic<Value = T>>(&self, exponent: u64, arith: &A) -> Matrix<T> {
+        let mut result = Matrix::identity(self.size, arith);
+        let mut square = self.clone();
+        let mut remaining = exponent;
+        while remaining > 0 {
+            if remaining % 2 == 1 {
+                result = result.mul(&square, arith);
+            }
+            remaining /= 2;
+            if remaining > 0 {
+                square = square.mul(&square, arith);
+            }
+        }
+        result
+    }
+
+    // multiplies the matrix by the given column vector
+    pub fn apply<A: Arithmetic<Value = T>>(&self, vector: &[T], arith: &A) -> Vec<T> {
+        assert_eq!(
+            self.size,
+            vector.len(),
+            "vector is the wrong size for the matrix"
+        );
+        let mut result = vec![arith.zero(); self.size];
+        for (row, total) in result.iter_mut().enumerate() {
+            for (col, value) in vector.iter().enumerate() {
+                let product = arith.mul(self.get(row, col), value);
+                *total = arith.add(total, &product);
+            }
+        }
+        result
+    }
+}
@@ -23,3 +23,8 @@ pub mod day6;
 pub mod day7;
 pub mod day8;
 pub mod day9;
+pub mod geometry;
+pub mod matrix;
+pub mod ocr;
+pub mod parse;
+pub mod search;
@@ -0,0 +1,84 @@
+// reads text drawn in the block capitals that Advent of Code puzzles use for answers which are
+// displayed rather than calculated. Each letter is 4 pixels wide and 6 high, and the letters
+// start every 5 columns. The one exception in that font is Y, which is drawn 5 wide and so runs
+// into the gap before the next letter - it can't be read at a fixed pitch, so it isn't here.
+
+const HEIGHT: usize = 6;
+const WIDTH: usize = 4;
+const PITCH: usize = 5;
+
+// the letters known to appear, drawn with # for lit pixels
+const GLYPHS: [(char, [&str; HEIGHT]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+// the pixel at the given position, treating anything outside the grid as unlit
+fn pixel(grid: &[Vec<bool>], row: usize, col: usize) -> bool {
+    grid.get(row)
+        .and_then(|pixels| pixels.get(col))
+        .copied()
+        .unwrap_or(false)
+}
+
+fn read_letter(grid: &[Vec<bool>], start: usize) -> Option<char> {
+    // the gap between letters must be blank, or this isn't the font we think it is
+    if (0..HEIGHT).any(|row| pixel(grid, row, start + WIDTH)) {
+        return None;
+    }
+    GLYPHS.iter().find_map(|(letter, glyph)| {
+        let matches = glyph.iter().enumerate().all(|(row, pattern)| {
+            pattern
+                .chars()
+                .enumerate()
+                .all(|(col, c)| (c == '#') == pixel(grid, row, start + col))
+        });
+        if matches {
+            Some(*letter)
+        } else {
+            None
+        }
+    })
+}
+
+// reads the letters from a grid of pixels given row by row, with the top-left corner of the
+// first letter at the top-left of the grid. Returns None if the grid is the wrong height or
+// contains anything other than known letters
+pub fn recognise(grid: &[Vec<bool>]) -> Option<String> {
+    if grid.len() != HEIGHT {
+        return None;
+    }
+    let width = grid.iter().map(|row| row.len()).max().unwrap_or(0);
+    if width == 0 {
+        return None;
+    }
+    (0..width)
+        .step_by(PITCH)
+        .map(|start| read_letter(grid, start))
+        .collect()
+}
+
+// draws a grid of pixels with # for lit pixels and . for unlit ones
+pub fn render(grid: &[Vec<bool>]) -> String {
+    let mut art = String::new();
+    for row in grid {
+        art.extend(row.iter().map(|&lit| if lit { '#' } else { '.' }));
+        art.push('\n');
+    }
+    art
+}
@@ -0,0 +1,382 @@
+use itertools::Itertools;
+use std::fmt;
+use std::str::FromStr;
+
+// a small set of parsers for reading puzzle input. Each parser is a function that reads something
+// from the start of the input it's given, and gives back what it read along with the input that's
+// left. Bigger parsers are made either by calling smaller ones one after the other, passing on
+// what's left each time, or with the functions here that take parsers and give back new ones
+
+// where a parser has got to. It keeps hold of all the text, so that errors can say which line and
+// column they happened at, and of where it has to stop - which might be the end of a line or a
+// section rather than the end of the text
+#[derive(Clone, Copy, Debug)]
+pub struct Input<'a> {
+    text: &'a str,
+    position: usize,
+    end: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    // both counted from 1, as a text editor would
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+// so that parse errors can be passed on with ? by anything that fails with a String
+impl From<ParseError> for String {
+    fn from(error: ParseError) -> String {
+        error.to_string()
+    }
+}
+
+pub type ParseResult<'a, T> = Result<(T, Input<'a>), ParseError>;
+
+// anything that can be used as a parser
+pub trait Parser<'a, T>: Fn(Input<'a>) -> ParseResult<'a, T> {}
+
+impl<'a, T, F: Fn(Input<'a>) -> ParseResult<'a, T>> Parser<'a, T> for F {}
+
+impl<'a> Input<'a> {
+    pub fn new(text: &'a str) -> Input<'a> {
+        Input {
+            text,
+            position: 0,
+            end: text.len(),
+        }
+    }
+
+    // the text still to be read
+    pub fn rest(&self) -> &'a str {
+        &self.text[self.position..self.end]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.position == self.end
+    }
+
+    fn advance(self, bytes: usize) -> Input<'a> {
+        Input {
+            position: self.position + bytes,
+            ..self
+        }
+    }
+
+    // just the next few bytes, which must be read without going past them
+    fn limit(self, bytes: usize) -> Input<'a> {
+        Input {
+            end: self.position + bytes,
+            ..self
+        }
+    }
+
+    // the text read between here and a later point in the same input
+    fn read_up_to(&self, later: &Input) -> &'a str {
+        &self.text[self.position..later.position]
+    }
+
+    // an error at the current position
+    pub fn error(&self, message: impl Into<String>) -> ParseError {
+        let before = &self.text[..self.position];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        ParseError {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            message: message.into(),
+        }
+    }
+
+    // an error saying what should have come next, and what came instead
+    pub fn expected(&self, what: &str) -> ParseError {
+        let found = match self.text[self.position..].chars().next() {
+            None => "the end of the input".to_owned(),
+            Some('\n') | Some('\r') => "the end of the line".to_owned(),
+            Some(c) => format!("{:?}", c),
+        };
+        self.error(format!("expected {}, found {}", what, found))
+    }
+}
+
+// reads the whole text with the parser, ignoring any blank space at the end
+pub fn parse_all<'a, T>(text: &'a str, parser: impl Parser<'a, T>) -> Result<T, ParseError> {
+    let input = Input::new(text.trim_end());
+    complete(input, &parser, "the end of the input")
+}
+
+// reads all of the input with the parser, failing if anything's left over
+fn complete<'a, T>(
+    input: Input<'a>,
+    parser: &impl Parser<'a, T>,
+    end: &str,
+) -> Result<T, ParseError> {
+    let (value, rest) = parser(input)?;
+    if !rest.is_empty() {
+        return Err(rest.expected(end));
+    }
+    Ok(value)
+}
+
+// exactly the given text, which is read and thrown away
+pub fn literal<'a>(text: &'static str) -> impl Parser<'a, ()> {
+    move |input: Input<'a>| {
+        if input.rest().starts_with(text) {
+            Ok(((), input.advance(text.len())))
+        } else {
+            Err(input.expected(&format!("{:?}", text)))
+        }
+    }
+}
+
+// a single character that passes the test. What's wanted is described for errors
+pub fn character<'a>(what: &'static str, test: impl Fn(char) -> bool) -> impl Parser<'a, char> {
+    move |input: Input<'a>| match input.rest().chars().next() {
+        Some(c) if test(c) => Ok((c, input.advance(c.len_utf8()))),
+        _ => Err(input.expected(what)),
+    }
+}
+
+// one or more characters that all pass the test. What's wanted is described for errors
+pub fn take_while<'a>(what: &'static str, test: impl Fn(char) -> bool) -> impl Parser<'a, &'a str> {
+    move |input: Input<'a>| {
+        let rest = input.rest();
+        let length = rest.find(|c| !test(c)).unwrap_or(rest.len());
+        if length == 0 {
+            return Err(input.expected(what));
+        }
+        Ok((&rest[..length], input.advance(length)))
+    }
+}
+
+// one or more letters
+pub fn word<'a>(input: Input<'a>) -> ParseResult<'a, &'a str> {
+    take_while("a word", |c| c.is_ascii_alphabetic())(input)
+}
+
+// one or more spaces
+pub fn spaces(input: Input<'_>) -> ParseResult<'_, ()> {
+    let (_, input) = take_while("a space", |c| c == ' ')(input)?;
+    Ok(((), input))
+}
+
+fn number<'a, T: FromStr>(start: Input<'a>, end: Input<'a>) -> ParseResult<'a, T> {
+    let text = start.read_up_to(&end);
+    match text.parse() {
+        Ok(number) => Ok((number, end)),
+        Err(_) => Err(start.error(format!("{} is out of range", text))),
+    }
+}
+
+// a number written with digits only
+pub fn unsigned<T: FromStr>(input: Input<'_>) -> ParseResult<'_, T> {
+    let (_, end) = take_while("a number", |c| c.is_ascii_digit())(input)?;
+    number(input, end)
+}
+
+// a number that might have a minus sign in front
+pub fn signed<T: FromStr>(input: Input<'_>) -> ParseResult<'_, T> {
+    let digits = match input.rest().strip_prefix('-') {
+        Some(_) => input.advance(1),
+        None => input,
+    };
+    let (_, end) = take_while("a number", |c| c.is_ascii_digit())(digits)?;
+    number(input, end)
+}
+
+// a range of numbers written as a..b, which might be negative
+pub fn range<T: FromStr>(input: Input<'_>) -> ParseResult<'_, (T, T)> {
+    let (start, input) = signed(input)?;
+    let (_, input) = literal("..")(input)?;
+    let (end, input) = signed(input)?;
+    Ok(((start, end), input))
+}
+
+// any one of the given words, giving the value that goes with it. If more than one of them
+// matches, the longest wins
+pub fn keyword<'a, T: Clone>(options: &'a [(&'a str, T)]) -> impl Parser<'a, T> {
+    move |input: Input<'a>| {
+        options
+            .iter()
+            .filter(|(word, _)| input.rest().starts_with(word))
+            .max_by_key(|(word, _)| word.len())
+            .map(|(word, value)| (value.clone(), input.advance(word.len())))
+            .ok_or_else(|| {
+                let words = options.iter().map(|(word, _)| format!("{:?}", word));
+                input.expected(&format!("one of {}", words.format(", ")))
+            })
+    }
+}
+
+// whatever the parser reads, if it can - otherwise nothing is read
+pub fn optional<'a, T>(parser: impl Parser<'a, T>) -> impl Parser<'a, Option<T>> {
+    move |input: Input<'a>| match parser(input) {
+        Ok((value, rest)) => Ok((Some(value), rest)),
+        Err(_) => Ok((None, input)),
+    }
+}
+
+// what the parser reads, turned into something else
+pub fn map<'a, T, U>(parser: impl Parser<'a, T>, convert: impl Fn(T) -> U) -> impl Parser<'a, U> {
+    move |input: Input<'a>| {
+        let (value, rest) = parser(input)?;
+        Ok((convert(value), rest))
+    }
+}
+
+// what the parser reads, turned into something else in a way that can fail. The error is put at
+// the start of what was read
+pub fn try_map<'a, T, U>(
+    parser: impl Parser<'a, T>,
+    convert: impl Fn(T) -> Result<U, String>,
+) -> impl Parser<'a, U> {
+    move |input: Input<'a>| {
+        let (value, rest) = parser(input)?;
+        match convert(value) {
+            Ok(converted) => Ok((converted, rest)),
+            Err(message) => Err(input.error(message)),
+        }
+    }
+}
+
+// one or more items with separators between them. The list ends when there's no separator, but
+// a separator must always be followed by another item, so that a bad item is reported where it
+// is rather than as something left over after a shorter list
+pub fn separated<'a, T, S>(
+    item: impl Parser<'a, T>,
+    separator: impl Parser<'a, S>,
+) -> impl Parser<'a, Vec<T>> {
+    move |input: Input<'a>| {
+        let (first, mut input) = item(input)?;
+        let mut items = vec![first];
+        while let Ok((_, after)) = separator(input) {
+            let (next, rest) = item(after)?;
+            items.push(next);
+            input = rest;
+        }
+        Ok((items, input))
+    }
+}
+
+// a single line, which must all be read by the parser. The newline at the end is read too
+pub fn line<'a, T>(parser: impl Parser<'a, T>) -> impl Parser<'a, T> {
+    move |input: Input<'a>| {
+        let rest = input.rest();
+        let (length, next) = match rest.find('\n') {
+            Some(newline) => (newline, newline + 1),
+            None => (rest.len(), rest.len()),
+        };
+        let length = length - usize::from(rest[..length].ends_with('\r'));
+        let value = complete(input.limit(length), &parser, "the end of the line")?;
+        Ok((value, input.advance(next)))
+    }
+}
+
+// every line that's left, each read by the parser. There must be at least one
+pub fn lines<'a, T>(parser: impl Parser<'a, T>) -> impl Parser<'a, Vec<T>> {
+    let parse_line = line(parser);
+    move |mut input: Input<'a>| {
+        let mut values = vec![];
+        loop {
+            let (value, rest) = parse_line(input)?;
+            values.push(value);
+            input = rest;
+            if input.is_empty() {
+                return Ok((values, input));
+            }
+        }
+    }
+}
+
+// a section of lines ending at a blank line, which must all be read by the parser. Any blank
+// lines after it are read too
+pub fn section<'a, T>(parser: impl Parser<'a, T>) -> impl Parser<'a, T> {
+    move |input: Input<'a>| {
+        let length = input
+            .rest()
+            .split_inclusive('\n')
+            .take_while(|line| !line.trim().is_empty())
+            .map(str::len)
+            .sum();
+        let section = input.limit(length);
+        let body_end = section.rest().trim_end_matches(['\n', '\r']).len();
+        let value = complete(section.limit(body_end), &parser, "the end of the section")?;
+        let after = input.advance(length);
+        let blank = after.rest().len() - after.rest().trim_start_matches(['\n', '\r']).len();
+        Ok((value, after.advance(blank)))
+    }
+}
+
+// every section that's left, each read by the parser. There must be at least one
+pub fn sections<'a, T>(parser: impl Parser<'a, T>) -> impl Parser<'a, Vec<T>> {
+    let parse_section = section(parser);
+    move |mut input: Input<'a>| {
+        let mut values = vec![];
+        loop {
+            let (value, rest) = parse_section(input)?;
+            values.push(value);
+            input = rest;
+            if input.is_empty() {
+                return Ok((values, input));
+            }
+        }
+    }
+}
+
+// a digit as its value, for reading grids of them
+pub fn digit(c: char) -> Option<u8> {
+    c.to_digit(10).map(|value| value as u8)
+}
+
+// a rectangle of characters, one row to a line, each turned into a cell by the given function -
+// which gives None for characters that can't be in the grid. What they can be is described for
+// errors
+pub fn grid<'a, T>(
+    what: &'static str,
+    cell: impl Fn(char) -> Option<T>,
+) -> impl Parser<'a, Vec<Vec<T>>> {
+    let row = line(move |mut input: Input<'a>| {
+        let mut cells = vec![];
+        while let Some(c) = input.rest().chars().next() {
+            match cell(c) {
+                Some(value) => cells.push(value),
+                None => return Err(input.expected(what)),
+            }
+            input = input.advance(c.len_utf8());
+        }
+        if cells.is_empty() {
+            return Err(input.expected(what));
+        }
+        Ok((cells, input))
+    });
+    move |mut input: Input<'a>| {
+        let mut rows: Vec<Vec<T>> = vec![];
+        loop {
+            let (cells, rest) = row(input)?;
+            if let Some(first) = rows.first() {
+                if cells.len() != first.len() {
+                    return Err(input.error(format!(
+                        "this row is {} long, but the first row is {} long",
+                        cells.len(),
+                        first.len()
+                    )));
+                }
+            }
+            rows.push(cells);
+            input = rest;
+            if input.is_empty() {
+                return Ok((rows, input));
+            }
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+
+// a graph given by which nodes can be reached in one step from each node, and what each of those
+// steps costs. The nodes are worked out as they're needed, so the graph doesn't have to be
+// small enough to list them all up front
+pub trait Graph {
+    type Node: Clone + Eq + Hash;
+
+    fn successors(&self, node: &Self::Node) -> Vec<(Self::Node, usize)>;
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Path<N> {
+    // every node along the path, including where it starts and ends
+    pub nodes: Vec<N>,
+    pub cost: usize,
+}
+
+// the nodes seen so far in a search, each given a number so that they only need to be stored
+// once, along with the node each was reached from
+struct Visited<N> {
+    nodes: Vec<N>,
+    ids: HashMap<N, usize>,
+    parents: Vec<Option<usize>>,
+}
+
+impl<N: Clone + Eq + Hash> Visited<N> {
+    fn new() -> Visited<N> {
+        Visited {
+            nodes: vec![],
+            ids: HashMap::new(),
+            parents: vec![],
+        }
+    }
+
+    // the number of the node, and whether it's new
+    fn id(&mut self, node: &N) -> (usize, bool) {
+        match self.ids.get(node) {
+            Some(&id) => (id, false),
+            None => {
+                let id = self.nodes.len();
+                self.nodes.push(node.clone());
+                self.ids.insert(node.clone(), id);
+                self.parents.push(None);
+                (id, true)
+            }
+        }
+    }
+
+    fn path_to(&self, end: usize, cost: usize) -> Path<N> {
+        let mut nodes = vec![];
+        let mut current = Some(end);
+        while let Some(id) = current {
+            nodes.push(self.nodes[id].clone());
+            current = self.parents[id];
+        }
+        nodes.reverse();
+        Path { nodes, cost }
+    }
+}
+
+// the cheapest path from the start to any node that's a goal. The heuristic must be consistent:
+// never more than the cost of a step plus the heuristic at the node it leads to, and 0 at goals.
+// Each node is finished with the first time it comes out of the queue, so otherwise the path
+// found might not be the cheapest. The closer it is to the true cost, the fewer nodes are looked at
+pub fn a_star<G: Graph>(
+    graph: &G,
+    start: G::Node,
+    is_goal: impl Fn(&G::Node) -> bool,
+    heuristic: impl Fn(&G::Node) -> usize,
+) -> Option<Path<G::Node>> {
+    let mut visited = Visited::new();
+    let mut costs = vec![0];
+    let mut done = vec![false];
+    visited.id(&start);
+    // ordered by the lowest estimate of the total cost of a path through each node
+    let mut queue = BinaryHeap::from([Reverse((heuristic(&start), 0))]);
+    while let Some(Reverse((_, id))) = queue.pop() {
+        // a node can be in the queue more than once if a cheaper way to it is found later, in
+        // which case all but the first time it comes out are ignored
+        if done[id] {
+            continue;
+        }
+        done[id] = true;
+        let node = visited.nodes[id].clone();
+        if is_goal(&node) {
+            return Some(visited.path_to(id, costs[id]));
+        }
+        for (next, step_cost) in graph.successors(&node) {
+            let cost = costs[id] + step_cost;
+            let (next_id, is_new) = visited.id(&next);
+            if is_new {
+                costs.push(cost);
+                done.push(false);
+            } else if done[next_id] || cost >= costs[next_id] {
+                continue;
+            }
+            costs[next_id] = cost;
+            visited.parents[next_id] = Some(id);
+            queue.push(Reverse((cost + heuristic(&next), next_id)));
+        }
+    }
+    None
+}
+
+// the cheapest path from the start to any node that's a goal
+pub fn dijkstra<G: Graph>(
+    graph: &G,
+    start: G::Node,
+    is_goal: impl Fn(&G::Node) -> bool,
+) -> Option<Path<G::Node>> {
+    a_star(graph, start, is_goal, |_| 0)
+}
+
+// the path from the start to any node that's a goal with the fewest steps, ignoring the costs of
+// the steps (although the cost of the path is still given)
+pub fn bfs<G: Graph>(
+    graph: &G,
+    start: G::Node,
+    is_goal: impl Fn(&G::Node) -> bool,
+) -> Option<Path<G::Node>> {
+    let mut visited = Visited::new();
+    let mut costs = vec![0];
+    let mut queue = VecDeque::from([visited.id(&start).0]);
+    while let Some(id) = queue.pop_front() {
+        let node = visited.nodes[id].clone();
+        if is_goal(&node) {
+            return Some(visited.path_to(id, costs[id]));
+        }
+        for (next, step_cost) in graph.successors(&node) {
+            let (next_id, is_new) = visited.id(&next);
+            if is_new {
+                costs.push(costs[id] + step_cost);
+                visited.parents[next_id] = Some(id);
+                queue.push_back(next_id);
+            }
+        }
+    }
+    None
+}
+
+// every node that can be reached from the start, in the order a breadth-first search finds them
+pub fn reachable<G: Graph>(graph: &G, start: G::Node) -> Vec<G::Node> {
+    let mut visited = Visited::new();
+    let mut queue = VecDeque::from([visited.id(&start).0]);
+    while let Some(id) = queue.pop_front() {
+        let node = visited.nodes[id].clone();
+        for (next, _) in graph.successors(&node) {
+            let (next_id, is_new) = visited.id(&next);
+            if is_new {
+                queue.push_back(next_id);
+            }
+        }
+    }
+    visited.nodes
+}
+
+// a node on the path taken so far, with the cost of getting to it and the nodes still to try
+// going to next from it
+type Frame<N> = (N, usize, Vec<(N, usize)>);
+
+pub struct AllPaths<'a, G: Graph, F> {
+    graph: &'a G,
+    is_goal: F,
+    stack: Vec<Frame<G::Node>>,
+    // if the start is a goal, it's a path of its own, and the only one
+    start_path: Option<Path<G::Node>>,
+}
+
+// goes through every path from the start to a goal one at a time, depth first. Paths stop at the
+// first goal they reach. Nodes aren't remembered between paths, so this only finishes if there
+// are no cycles that can be reached from the start - the graph's nodes need to keep track of
+// anything that stops a path going round in circles
+pub fn all_paths<G: Graph, F: Fn(&G::Node) -> bool>(
+    graph: &G,
+    start: G::Node,
+    is_goal: F,
+) -> AllPaths<'_, G, F> {
+    let mut paths = AllPaths {
+        graph,
+        is_goal,
+        stack: vec![],
+        start_path: None,
+    };
+    if (paths.is_goal)(&start) {
+        paths.start_path = Some(Path {
+            nodes: vec![start],
+            cost: 0,
+        });
+    } else {
+        paths.push(start, 0);
+    }
+    paths
+}
+
+impl<G: Graph, F> AllPaths<'_, G, F> {
+    fn push(&mut self, node: G::Node, cost: usize) {
+        let mut successors = self.graph.successors(&node);
+        // they're taken from the end, so reversed to try them in order
+        successors.reverse();
+        self.stack.push((node, cost, successors));
+    }
+}
+
+impl<G: Graph, F: Fn(&G::Node) -> bool> Iterator for AllPaths<'_, G, F> {
+    type Item = Path<G::Node>;
+
+    fn next(&mut self) -> Option<Path<G::Node>> {
+        if let Some(path) = self.start_path.take() {
+            return Some(path);
+        }
+        loop {
+            let (_, cost, to_try) = self.stack.last_mut()?;
+            let cost = *cost;
+            match to_try.pop() {
+                None => {
+                    self.stack.pop();
+                }
+                Some((next, step_cost)) => {
+                    let cost = cost + step_cost;
+                    if (self.is_goal)(&next) {
+                        let mut nodes: Vec<G::Node> =
+                            self.stack.iter().map(|(node, _, _)| node.clone()).collect();
+                        nodes.push(next);
+                        return Some(Path { nodes, cost });
+                    }
+                    self.push(next, cost);
+                }
+            }
+        }
+    }
+}